@@ -0,0 +1,174 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+use crate::errors::DreamersError;
+use crate::errors::ErrorCode;
+use crate::state::DreamerState;
+use crate::state_config::StateConfig;
+use crate::state_config::STATE_CONFIG_SIZE;
+use crate::utils::create_marker_account;
+
+pub const ALLOWLIST_CLAIM_SIZE: usize = 8 + 1;
+
+/// Marks a destination as paid for an allowlist round.
+#[account]
+pub struct AllowlistClaim {
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+pub struct SetAllowlist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_dreamer: Account<'info, DreamerState>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_dreamer.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Account<'info, StateConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Verifies a proof for `leaf` against `root`, hashing sorted pairs with keccak.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            keccak::hashv(&[&node, sibling]).0
+        } else {
+            keccak::hashv(&[sibling, &node]).0
+        }
+    });
+    computed == root
+}
+
+/// Checks `destination` against the allowlist in `state_config` and creates its
+/// claim marker for the current round. Does nothing when no allowlist is set.
+pub fn assert_allowlisted_destination<'info>(
+    state: &Pubkey,
+    state_config: &AccountInfo<'info>,
+    destination: &Pubkey,
+    proof: &[[u8; 32]],
+    claim_marker: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let (root, round) = match StateConfig::load(state_config)? {
+        Some(StateConfig {
+            allowlist_root: Some(root),
+            allowlist_round,
+            ..
+        }) => (root, allowlist_round),
+        _ => return Ok(()),
+    };
+
+    let leaf = keccak::hashv(&[destination.as_ref()]).0;
+    require!(
+        verify_merkle_proof(proof, root, leaf),
+        ErrorCode::InvalidAllowlistProof
+    );
+
+    let round_bytes = round.to_le_bytes();
+    let (marker_address, bump) = Pubkey::find_program_address(
+        &[b"allowlist_claim", state.as_ref(), &round_bytes, destination.as_ref()],
+        &crate::id(),
+    );
+    require_keys_eq!(
+        claim_marker.key(),
+        marker_address,
+        ErrorCode::InvalidAllowlistClaimMarker
+    );
+    require!(
+        claim_marker.data_is_empty(),
+        ErrorCode::AllowlistAlreadyClaimed
+    );
+
+    let marker_seeds: &[&[u8]] = &[
+        b"allowlist_claim",
+        state.as_ref(),
+        &round_bytes,
+        destination.as_ref(),
+        &[bump],
+    ];
+    create_marker_account(
+        claim_marker,
+        payer,
+        system_program,
+        ALLOWLIST_CLAIM_SIZE,
+        marker_seeds,
+    )?;
+    AllowlistClaim { bump }.try_serialize(&mut &mut claim_marker.try_borrow_mut_data()?[..])?;
+
+    Ok(())
+}
+
+/// Replaces the winners list. Each new root starts a new round so wallets from
+/// earlier lists can be paid again.
+pub fn set_allowlist(ctx: Context<SetAllowlist>, root: Option<[u8; 32]>) -> Result<()> {
+    let state_config = &mut ctx.accounts.state_config;
+    state_config.bump = *ctx.bumps.get("state_config").unwrap();
+    state_config.state = ctx.accounts.pda_dreamer.key();
+    state_config.allowlist_root = root;
+    state_config.allowlist_round = state_config.allowlist_round.checked_add(1).unwrap();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        if a <= b {
+            keccak::hashv(&[&a, &b]).0
+        } else {
+            keccak::hashv(&[&b, &a]).0
+        }
+    }
+
+    fn leaves() -> [[u8; 32]; 4] {
+        [1u8, 2, 3, 4].map(|wallet| keccak::hash(&[wallet; 32]).0)
+    }
+
+    #[test]
+    fn verifies_every_leaf() {
+        let [a, b, c, d] = leaves();
+        let ab = hash_pair(a, b);
+        let cd = hash_pair(c, d);
+        let root = hash_pair(ab, cd);
+
+        assert!(verify_merkle_proof(&[b, cd], root, a));
+        assert!(verify_merkle_proof(&[a, cd], root, b));
+        assert!(verify_merkle_proof(&[d, ab], root, c));
+        assert!(verify_merkle_proof(&[c, ab], root, d));
+    }
+
+    #[test]
+    fn rejects_proof_for_other_leaf() {
+        let [a, b, c, d] = leaves();
+        let root = hash_pair(hash_pair(a, b), hash_pair(c, d));
+
+        assert!(!verify_merkle_proof(&[b, hash_pair(c, d)], root, c));
+        assert!(!verify_merkle_proof(&[], root, a));
+    }
+
+    #[test]
+    fn proof_levels_must_be_in_order() {
+        let [a, b, c, d] = leaves();
+        let cd = hash_pair(c, d);
+        let root = hash_pair(hash_pair(a, b), cd);
+
+        assert!(!verify_merkle_proof(&[cd, b], root, a));
+    }
+
+    #[test]
+    fn single_leaf_tree_needs_no_proof() {
+        let [a, ..] = leaves();
+        assert!(verify_merkle_proof(&[], a, a));
+    }
+}
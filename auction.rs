@@ -0,0 +1,527 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::deposit::record_withdrawal;
+use crate::errors::DreamersError;
+use crate::errors::ErrorCode;
+use crate::pnft::invoke_transfer_asset;
+use crate::pnft::PNFTTransferAccounts;
+use crate::state::SwappedState;
+use crate::state_config::consume_rate_limit;
+use crate::state_config::record_closed_position;
+use crate::state_config::record_open_position;
+use crate::state_config::StateConfig;
+use crate::state_config::STATE_CONFIG_SIZE;
+use crate::utils::close_escrow_token_account;
+use crate::utils::transfer_lamports_from_pda;
+
+pub const AUCTION_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 4 + 8 + (1 + 32);
+
+/// English auction for an asset escrowed from a swap PDA. The highest bid is
+/// escrowed in the auction account and paid to `seller` when it is settled.
+#[account]
+pub struct Auction {
+    pub bump: u8,
+    pub state: Pubkey,
+    /// Swap authority that created the auction and may cancel it
+    pub authority: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub reserve_price: u64,
+    pub min_increment: u64,
+    pub end_time: i64,
+    /// Bids placed within this many seconds of `end_time` push it back by as much
+    pub extension_seconds: u32,
+    pub highest_bid: u64,
+    pub highest_bidder: Option<Pubkey>,
+}
+
+impl Auction {
+    /// Signer seeds of the auction PDA, which owns the asset escrow
+    pub fn seeds(&self) -> [&[u8]; 4] {
+        [
+            b"auction",
+            self.state.as_ref(),
+            self.mint.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
+
+#[event]
+pub struct BidPlaced {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct AuctionSettled {
+    pub auction: Pubkey,
+    pub winner: Option<Pubkey>,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AuctionCancelled {
+    pub auction: Pubkey,
+    pub refunded_bidder: Option<Pubkey>,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreateAuctionArgs {
+    pub seller: Pubkey,
+    pub reserve_price: u64,
+    pub min_increment: u64,
+    pub end_time: i64,
+    pub extension_seconds: u32,
+}
+
+#[derive(Accounts)]
+pub struct CreateAuction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = pda_swapped.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_swapped: Box<Account<'info, SwappedState>>,
+    #[account(
+        init,
+        payer = authority,
+        space = AUCTION_SIZE,
+        seeds = [b"auction", pda_swapped.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        token_account.owner == pda_swapped.key()
+            && token_account.mint == mint.key()
+            && token_account.amount == 1
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = auction,
+    )]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_swapped.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Box<Account<'info, StateConfig>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(mut)]
+    pub auction: Box<Account<'info, Auction>>,
+    /// CHECK: This is not dangerous because it must match the current highest bidder
+    #[account(mut)]
+    pub previous_bidder: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Without bids the asset goes back to the swap PDA, so `winner` is then the
+/// swap PDA itself.
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        constraint = pda_swapped.key() == auction.state @ ErrorCode::InvalidAuction,
+        constraint = pda_swapped.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_swapped: Box<Account<'info, SwappedState>>,
+    #[account(
+        mut,
+        close = authority,
+        has_one = authority @ ErrorCode::InvalidAuction,
+        has_one = seller @ ErrorCode::InvalidAuction,
+        has_one = mint @ ErrorCode::InvalidAuction,
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+    /// CHECK: This is not dangerous because it must match the auction authority
+    #[account(mut)]
+    pub authority: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because it must match the auction seller
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because it must be the highest bidder, or the swap PDA without bids
+    #[account(constraint =
+        winner.key() == auction.highest_bidder.unwrap_or(auction.state)
+        @ ErrorCode::InvalidAuction)]
+    pub winner: UncheckedAccount<'info>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        escrow_token_account.owner == auction.key() && escrow_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we only update it in consume_rate_limit, record_withdrawal and record_closed_position
+    #[account(mut, seeds = [b"state_config", pda_swapped.key().as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we only update it in record_withdrawal
+    #[account(mut, seeds = [b"deposit", pda_swapped.key().as_ref(), mint.key().as_ref()], bump)]
+    pub deposit_record: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = winner,
+    )]
+    pub winner_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub winner_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        constraint = pda_swapped.key() == auction.state @ ErrorCode::InvalidAuction,
+        constraint = pda_swapped.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_swapped: Box<Account<'info, SwappedState>>,
+    #[account(
+        mut,
+        close = authority,
+        has_one = authority @ DreamersError::Unauthorized,
+        has_one = mint @ ErrorCode::InvalidAuction,
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+    /// CHECK: This is not dangerous because it must match the current highest bidder
+    #[account(mut)]
+    pub highest_bidder: UncheckedAccount<'info>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        escrow_token_account.owner == auction.key() && escrow_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we only update it in record_closed_position
+    #[account(mut, seeds = [b"state_config", pda_swapped.key().as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = pda_swapped,
+    )]
+    pub token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Escrows the asset from the swap PDA in an account owned by the auction.
+pub fn create_auction(ctx: Context<CreateAuction>, args: CreateAuctionArgs) -> Result<()> {
+    msg!(format!("Creating Auction From Swap Account").as_str());
+
+    require!(
+        args.end_time > Clock::get()?.unix_timestamp,
+        ErrorCode::InvalidAuction
+    );
+
+    let pda_swapped = &ctx.accounts.pda_swapped;
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.token_account.to_account_info(),
+            token_owner: pda_swapped.to_account_info(),
+            destination_token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: ctx.accounts.auction.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.token_record.to_account_info(),
+            destination_token_record: ctx.accounts.escrow_token_record.to_account_info(),
+            authority: pda_swapped.to_account_info(),
+            payer: ctx.accounts.authority.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[&[
+            b"swapped",
+            pda_swapped.authority.key().as_ref(),
+            &[pda_swapped.bump],
+        ]],
+    )?;
+
+    let auction = &mut ctx.accounts.auction;
+    auction.bump = *ctx.bumps.get("auction").unwrap();
+    auction.state = ctx.accounts.pda_swapped.key();
+    auction.authority = ctx.accounts.authority.key();
+    auction.seller = args.seller;
+    auction.mint = ctx.accounts.mint.key();
+    auction.reserve_price = args.reserve_price;
+    auction.min_increment = args.min_increment;
+    auction.end_time = args.end_time;
+    auction.extension_seconds = args.extension_seconds;
+    auction.highest_bid = 0;
+    auction.highest_bidder = None;
+
+    record_open_position(
+        &mut ctx.accounts.state_config,
+        *ctx.bumps.get("state_config").unwrap(),
+        ctx.accounts.pda_swapped.key(),
+    );
+    Ok(())
+}
+
+pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let auction = &mut ctx.accounts.auction;
+    require!(now < auction.end_time, ErrorCode::AuctionEnded);
+
+    let min_bid = match auction.highest_bidder {
+        Some(_) => auction
+            .highest_bid
+            .checked_add(auction.min_increment)
+            .ok_or(error!(ErrorCode::BidTooLow))?,
+        None => auction.reserve_price,
+    };
+    require!(amount >= min_bid && amount > 0, ErrorCode::BidTooLow);
+
+    invoke(
+        &system_instruction::transfer(&ctx.accounts.bidder.key(), &auction.key(), amount),
+        &[
+            ctx.accounts.bidder.to_account_info(),
+            auction.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    if let Some(previous_bidder) = auction.highest_bidder {
+        require_keys_eq!(
+            ctx.accounts.previous_bidder.key(),
+            previous_bidder,
+            ErrorCode::InvalidPreviousBidder
+        );
+        transfer_lamports_from_pda(
+            &auction.to_account_info(),
+            &ctx.accounts.previous_bidder.to_account_info(),
+            auction.highest_bid,
+        )?;
+    }
+
+    auction.highest_bid = amount;
+    auction.highest_bidder = Some(ctx.accounts.bidder.key());
+    let extension = auction.extension_seconds as i64;
+    if auction.end_time - now < extension {
+        auction.end_time = now + extension;
+    }
+
+    emit!(BidPlaced {
+        auction: auction.key(),
+        bidder: ctx.accounts.bidder.key(),
+        amount,
+        end_time: auction.end_time,
+    });
+    Ok(())
+}
+
+/// Sends the escrowed asset to the highest bidder and the winning bid to the
+/// seller. Without bids the asset goes back to the swap PDA.
+pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+    msg!(format!("Settling Auction").as_str());
+
+    let auction = &ctx.accounts.auction;
+    require!(
+        Clock::get()?.unix_timestamp >= auction.end_time,
+        ErrorCode::AuctionNotEnded
+    );
+
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            token_owner: auction.to_account_info(),
+            destination_token_account: ctx.accounts.winner_token_account.to_account_info(),
+            destination: ctx.accounts.winner.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.escrow_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.winner_token_record.to_account_info(),
+            authority: auction.to_account_info(),
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[&auction.seeds()],
+    )?;
+    close_escrow_token_account(
+        &ctx.accounts.escrow_token_account.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        &auction.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &[&auction.seeds()],
+    )?;
+
+    if auction.highest_bidder.is_some() {
+        transfer_lamports_from_pda(
+            &auction.to_account_info(),
+            &ctx.accounts.seller.to_account_info(),
+            auction.highest_bid,
+        )?;
+        consume_rate_limit(&ctx.accounts.state_config)?;
+        record_withdrawal(&ctx.accounts.deposit_record, &ctx.accounts.state_config)?;
+    }
+
+    record_closed_position(&ctx.accounts.state_config)?;
+
+    emit!(AuctionSettled {
+        auction: auction.key(),
+        winner: auction.highest_bidder,
+        amount: auction.highest_bid,
+    });
+    Ok(())
+}
+
+/// Returns the escrowed asset to the swap PDA and the highest bid to its bidder.
+/// Only possible before the auction ends.
+pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+    msg!(format!("Cancelling Auction").as_str());
+
+    let auction = &ctx.accounts.auction;
+    require!(
+        Clock::get()?.unix_timestamp < auction.end_time,
+        ErrorCode::AuctionEnded
+    );
+
+    if let Some(highest_bidder) = auction.highest_bidder {
+        require_keys_eq!(
+            ctx.accounts.highest_bidder.key(),
+            highest_bidder,
+            ErrorCode::InvalidPreviousBidder
+        );
+        transfer_lamports_from_pda(
+            &auction.to_account_info(),
+            &ctx.accounts.highest_bidder.to_account_info(),
+            auction.highest_bid,
+        )?;
+    }
+
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            token_owner: auction.to_account_info(),
+            destination_token_account: ctx.accounts.token_account.to_account_info(),
+            destination: ctx.accounts.pda_swapped.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.escrow_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.token_record.to_account_info(),
+            authority: auction.to_account_info(),
+            payer: ctx.accounts.authority.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[&auction.seeds()],
+    )?;
+    close_escrow_token_account(
+        &ctx.accounts.escrow_token_account.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        &auction.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &[&auction.seeds()],
+    )?;
+    record_closed_position(&ctx.accounts.state_config)?;
+
+    emit!(AuctionCancelled {
+        auction: auction.key(),
+        refunded_bidder: auction.highest_bidder,
+        amount: auction.highest_bid,
+    });
+    Ok(())
+}
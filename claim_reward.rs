@@ -0,0 +1,187 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::allowlist::verify_merkle_proof;
+use crate::deposit::clear_held;
+use crate::errors::DreamersError;
+use crate::errors::ErrorCode;
+use crate::pnft::invoke_transfer_asset;
+use crate::pnft::PNFTTransferAccounts;
+use crate::state::DreamerState;
+use crate::state_config::StateConfig;
+use crate::state_config::STATE_CONFIG_SIZE;
+
+pub const REWARD_CLAIM_SIZE: usize = 8 + 1;
+
+/// Marks a (wallet, mint) pair as claimed for a claim round.
+#[account]
+pub struct RewardClaim {
+    pub bump: u8,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub state: Pubkey,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub round: u64,
+}
+
+#[derive(Accounts)]
+pub struct SetClaimRoot<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_dreamer: Account<'info, DreamerState>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_dreamer.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Account<'info, StateConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReward<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    #[account(constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated)]
+    pub pda_dreamer: Box<Account<'info, DreamerState>>,
+    #[account(
+        mut,
+        seeds = [b"state_config", pda_dreamer.key().as_ref()],
+        bump = state_config.bump,
+        constraint = state_config.claim_root.is_some() @ ErrorCode::RewardClaimsClosed,
+    )]
+    pub state_config: Box<Account<'info, StateConfig>>,
+    #[account(
+        init,
+        payer = recipient,
+        space = REWARD_CLAIM_SIZE,
+        seeds = [
+            b"reward_claim",
+            pda_dreamer.key().as_ref(),
+            &state_config.claim_round.to_le_bytes(),
+            recipient.key().as_ref(),
+            mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub reward_claim: Box<Account<'info, RewardClaim>>,
+    pub mint: Box<Account<'info, Mint>>,
+    /// CHECK: This is not dangerous because we only update it in clear_held
+    #[account(mut, seeds = [b"deposit", pda_dreamer.key().as_ref(), mint.key().as_ref()], bump)]
+    pub deposit_record: UncheckedAccount<'info>,
+    #[account(mut, constraint =
+        token_account.owner == pda_dreamer.key() && token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub recipient_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Replaces the list of (wallet, mint) pairs winners may claim. Each new root
+/// starts a new claim round, `None` closes claims.
+pub fn set_claim_root(ctx: Context<SetClaimRoot>, root: Option<[u8; 32]>) -> Result<()> {
+    let state_config = &mut ctx.accounts.state_config;
+    state_config.bump = *ctx.bumps.get("state_config").unwrap();
+    state_config.state = ctx.accounts.pda_dreamer.key();
+    state_config.claim_root = root;
+    state_config.claim_round = state_config.claim_round.checked_add(1).unwrap();
+    Ok(())
+}
+
+pub fn claim_reward(ctx: Context<ClaimReward>, proof: Vec<[u8; 32]>) -> Result<()> {
+    msg!(format!("Claiming Reward From Dreamer Account").as_str());
+
+    let recipient = ctx.accounts.recipient.key();
+    let mint = ctx.accounts.mint.key();
+    let leaf = keccak::hashv(&[recipient.as_ref(), mint.as_ref()]).0;
+    require!(
+        verify_merkle_proof(&proof, ctx.accounts.state_config.claim_root.unwrap(), leaf),
+        ErrorCode::InvalidRewardClaimProof
+    );
+    ctx.accounts.reward_claim.bump = *ctx.bumps.get("reward_claim").unwrap();
+    ctx.accounts
+        .state_config
+        .consume_transfer(Clock::get()?.unix_timestamp)?;
+
+    let pda_dreamer = &ctx.accounts.pda_dreamer;
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.token_account.to_account_info(),
+            token_owner: pda_dreamer.to_account_info(),
+            destination_token_account: ctx.accounts.recipient_token_account.to_account_info(),
+            destination: ctx.accounts.recipient.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.token_record.to_account_info(),
+            destination_token_record: ctx.accounts.recipient_token_record.to_account_info(),
+            authority: pda_dreamer.to_account_info(),
+            payer: ctx.accounts.recipient.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[&[
+            b"dreamer",
+            &[pda_dreamer.id],
+            pda_dreamer.authority.key().as_ref(),
+            &[pda_dreamer.bump],
+        ]],
+    )?;
+    if clear_held(&ctx.accounts.deposit_record)? {
+        let state_config = &mut ctx.accounts.state_config;
+        state_config.held_assets = state_config.held_assets.saturating_sub(1);
+    }
+
+    emit!(RewardClaimed {
+        state: pda_dreamer.key(),
+        mint,
+        recipient,
+        round: ctx.accounts.state_config.claim_round,
+    });
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::errors::DreamersError;
+use crate::errors::ErrorCode;
+use crate::state::DreamerState;
+use crate::state::SwappedState;
+use crate::state_config::StateConfig;
+
+/// Remaining accounts are every token account owned by the dreamer PDA. They
+/// must be empty and are closed along with the state and its config.
+#[derive(Accounts)]
+pub struct CloseDreamer<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, close = authority, has_one = authority @ DreamersError::Unauthorized)]
+    pub pda_dreamer: Account<'info, DreamerState>,
+    /// CHECK: This is not dangerous because we validate and close it in close_state_config
+    #[account(mut, seeds = [b"state_config", pda_dreamer.key().as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Remaining accounts are every token account owned by the swapped PDA. They
+/// must be empty and are closed along with the state and its config.
+#[derive(Accounts)]
+pub struct CloseSwapped<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, close = authority, has_one = authority @ DreamersError::Unauthorized)]
+    pub pda_swapped: Account<'info, SwappedState>,
+    /// CHECK: This is not dangerous because we validate and close it in close_state_config
+    #[account(mut, seeds = [b"state_config", pda_swapped.key().as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Checks that each token account is owned by `state` and holds nothing, then
+/// closes it to `authority`.
+fn close_empty_token_accounts<'info>(
+    state: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    token_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    for info in token_accounts {
+        let token_account = Account::<TokenAccount>::try_from(info)
+            .map_err(|_| error!(ErrorCode::InvalidStateTokenAccount))?;
+        require_keys_eq!(
+            token_account.owner,
+            state.key(),
+            ErrorCode::InvalidStateTokenAccount
+        );
+        require!(token_account.amount == 0, ErrorCode::CannotCloseStateWithAssets);
+
+        invoke_signed(
+            &spl_token::instruction::close_account(
+                &token_program.key(),
+                &info.key(),
+                &authority.key(),
+                &state.key(),
+                &[],
+            )?,
+            &[
+                token_program.clone(),
+                info.clone(),
+                authority.clone(),
+                state.clone(),
+            ],
+            signer_seeds,
+        )?;
+    }
+    Ok(())
+}
+
+/// Fails while the state still holds deposited assets or has open raffles,
+/// auctions, listings or vesting schedules, then closes its config to
+/// `authority`. States that never had a config have nothing to check.
+fn close_state_config<'info>(
+    state_config: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+) -> Result<()> {
+    let config = match StateConfig::load(state_config)? {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+    require!(
+        config.held_assets == 0,
+        ErrorCode::CannotCloseStateWithAssets
+    );
+    require!(
+        config.open_positions == 0,
+        ErrorCode::CannotCloseStateWithPositions
+    );
+    Account::<StateConfig>::try_from(state_config)?.close(authority.clone())
+}
+
+pub fn close_dreamer<'info>(ctx: Context<'_, '_, '_, 'info, CloseDreamer<'info>>) -> Result<()> {
+    msg!(format!("Closing Dreamer Account").as_str());
+
+    close_state_config(
+        &ctx.accounts.state_config,
+        &ctx.accounts.authority.to_account_info(),
+    )?;
+
+    let pda_dreamer = &ctx.accounts.pda_dreamer;
+    close_empty_token_accounts(
+        &pda_dreamer.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.remaining_accounts,
+        &[&[
+            b"dreamer",
+            &[pda_dreamer.id],
+            pda_dreamer.authority.as_ref(),
+            &[pda_dreamer.bump],
+        ]],
+    )
+}
+
+pub fn close_swapped<'info>(ctx: Context<'_, '_, '_, 'info, CloseSwapped<'info>>) -> Result<()> {
+    msg!(format!("Closing Swapped Account").as_str());
+
+    close_state_config(
+        &ctx.accounts.state_config,
+        &ctx.accounts.authority.to_account_info(),
+    )?;
+
+    let pda_swapped = &ctx.accounts.pda_swapped;
+    close_empty_token_accounts(
+        &pda_swapped.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.remaining_accounts,
+        &[&[b"swapped", pda_swapped.authority.as_ref(), &[pda_swapped.bump]]],
+    )
+}
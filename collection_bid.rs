@@ -0,0 +1,189 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::errors::ErrorCode;
+use crate::pnft::invoke_transfer_asset;
+use crate::pnft::PNFTTransferAccounts;
+use crate::utils::assert_metadata_for_mint;
+use crate::utils::transfer_lamports_from_pda;
+
+pub const COLLECTION_BID_SIZE: usize = 8 + 1 + 32 + 32 + 8;
+
+/// Offer of `amount` lamports, escrowed in this account, for any NFT from a
+/// verified collection.
+#[account]
+pub struct CollectionBid {
+    pub bump: u8,
+    pub bidder: Pubkey,
+    pub collection: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CollectionBidFilled {
+    pub collection_bid: Pubkey,
+    pub bidder: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(collection: Pubkey)]
+pub struct PlaceCollectionBid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(
+        init,
+        payer = bidder,
+        space = COLLECTION_BID_SIZE,
+        seeds = [b"collection_bid", bidder.key().as_ref(), collection.as_ref()],
+        bump,
+    )]
+    pub collection_bid: Account<'info, CollectionBid>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelCollectionBid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(mut, close = bidder, has_one = bidder @ ErrorCode::InvalidCollectionBid)]
+    pub collection_bid: Account<'info, CollectionBid>,
+}
+
+#[derive(Accounts)]
+pub struct FillCollectionBid<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(mut, close = bidder, has_one = bidder @ ErrorCode::InvalidCollectionBid)]
+    pub collection_bid: Box<Account<'info, CollectionBid>>,
+    /// CHECK: This is not dangerous because it must match the bid bidder
+    #[account(mut)]
+    pub bidder: UncheckedAccount<'info>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        seller_token_account.owner == seller.key() && seller_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub seller_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = bidder,
+    )]
+    pub bidder_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub seller_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub bidder_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in assert_metadata_for_mint
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn place_collection_bid(
+    ctx: Context<PlaceCollectionBid>,
+    collection: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidCollectionBid);
+
+    invoke(
+        &system_instruction::transfer(
+            &ctx.accounts.bidder.key(),
+            &ctx.accounts.collection_bid.key(),
+            amount,
+        ),
+        &[
+            ctx.accounts.bidder.to_account_info(),
+            ctx.accounts.collection_bid.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    let collection_bid = &mut ctx.accounts.collection_bid;
+    collection_bid.bump = *ctx.bumps.get("collection_bid").unwrap();
+    collection_bid.bidder = ctx.accounts.bidder.key();
+    collection_bid.collection = collection;
+    collection_bid.amount = amount;
+    Ok(())
+}
+
+/// Closing the bid returns the escrowed lamports to the bidder with the rent.
+pub fn cancel_collection_bid(_ctx: Context<CancelCollectionBid>) -> Result<()> {
+    Ok(())
+}
+
+/// Sends the seller's NFT to the bidder and pays the seller the escrowed bid.
+pub fn fill_collection_bid(ctx: Context<FillCollectionBid>) -> Result<()> {
+    msg!(format!("Filling Collection Bid").as_str());
+
+    let collection_bid = &ctx.accounts.collection_bid;
+    let metadata = assert_metadata_for_mint(&ctx.accounts.metadata, &ctx.accounts.mint.key())?;
+    require!(
+        metadata.collection.as_ref().map_or(false, |collection| {
+            collection.verified && collection.key == collection_bid.collection
+        }),
+        ErrorCode::InvalidCollectionBidMint
+    );
+
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.seller_token_account.to_account_info(),
+            token_owner: ctx.accounts.seller.to_account_info(),
+            destination_token_account: ctx.accounts.bidder_token_account.to_account_info(),
+            destination: ctx.accounts.bidder.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.seller_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.bidder_token_record.to_account_info(),
+            authority: ctx.accounts.seller.to_account_info(),
+            payer: ctx.accounts.seller.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[],
+    )?;
+
+    transfer_lamports_from_pda(
+        &collection_bid.to_account_info(),
+        &ctx.accounts.seller.to_account_info(),
+        collection_bid.amount,
+    )?;
+
+    emit!(CollectionBidFilled {
+        collection_bid: collection_bid.key(),
+        bidder: collection_bid.bidder,
+        seller: ctx.accounts.seller.key(),
+        mint: ctx.accounts.mint.key(),
+        amount: collection_bid.amount,
+    });
+    Ok(())
+}
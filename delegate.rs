@@ -0,0 +1,233 @@
+use anchor_lang::prelude::*;
+use mpl_token_metadata::instruction::DelegateArgs;
+use mpl_token_metadata::instruction::LockArgs;
+use mpl_token_metadata::instruction::MetadataInstruction;
+use mpl_token_metadata::instruction::RevokeArgs;
+use mpl_token_metadata::instruction::UnlockArgs;
+use mpl_token_metadata::state::TokenMetadataAccount;
+use mpl_token_metadata::state::TokenRecord;
+use mpl_token_metadata::state::TokenState;
+use solana_program::instruction::Instruction;
+use solana_program::program::invoke_signed;
+
+use crate::errors::ErrorCode;
+
+/// Accounts shared by the Token Metadata delegate, revoke, lock and unlock instructions.
+pub struct PNFTAccounts<'info> {
+    pub token_owner: AccountInfo<'info>,
+    pub token_account: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub edition: AccountInfo<'info>,
+    pub token_record: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub authorization_rules_program: AccountInfo<'info>,
+    pub authorization_rules: AccountInfo<'info>,
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+/// Fails if the pNFT behind `token_record` is locked. Accounts without a token
+/// record (regular NFTs) are never locked.
+pub fn assert_token_unlocked(token_record: &AccountInfo) -> Result<()> {
+    if *token_record.owner != mpl_token_metadata::id() || token_record.data_is_empty() {
+        return Ok(());
+    }
+    let token_record = TokenRecord::from_account_info(token_record)?;
+    require!(token_record.state != TokenState::Locked, ErrorCode::TokenLocked);
+    Ok(())
+}
+
+pub fn delegate_pnft<'info>(
+    accounts: &PNFTAccounts<'info>,
+    delegate: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    args: DelegateArgs,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_signed(
+        &Instruction {
+            program_id: mpl_token_metadata::id(),
+            accounts: delegate_account_metas(accounts, delegate, authority),
+            data: MetadataInstruction::Delegate(args).try_to_vec().unwrap(),
+        },
+        &delegate_account_infos(accounts, delegate, authority),
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+pub fn revoke_pnft<'info>(
+    accounts: &PNFTAccounts<'info>,
+    delegate: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    args: RevokeArgs,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_signed(
+        &Instruction {
+            program_id: mpl_token_metadata::id(),
+            accounts: delegate_account_metas(accounts, delegate, authority),
+            data: MetadataInstruction::Revoke(args).try_to_vec().unwrap(),
+        },
+        &delegate_account_infos(accounts, delegate, authority),
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+pub fn lock_pnft<'info>(
+    accounts: &PNFTAccounts<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_signed(
+        &Instruction {
+            program_id: mpl_token_metadata::id(),
+            accounts: lock_account_metas(accounts, authority),
+            data: MetadataInstruction::Lock(LockArgs::V1 {
+                authorization_data: None,
+            })
+            .try_to_vec()
+            .unwrap(),
+        },
+        &lock_account_infos(accounts, authority),
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+pub fn unlock_pnft<'info>(
+    accounts: &PNFTAccounts<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_signed(
+        &Instruction {
+            program_id: mpl_token_metadata::id(),
+            accounts: lock_account_metas(accounts, authority),
+            data: MetadataInstruction::Unlock(UnlockArgs::V1 {
+                authorization_data: None,
+            })
+            .try_to_vec()
+            .unwrap(),
+        },
+        &lock_account_infos(accounts, authority),
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+fn delegate_account_metas(
+    accounts: &PNFTAccounts,
+    delegate: &AccountInfo,
+    authority: &AccountInfo,
+) -> Vec<AccountMeta> {
+    vec![
+        // #[account(0, optional, writable, name="delegate_record", desc="Delegate record account")]
+        AccountMeta::new_readonly(accounts.token_metadata_program.key(), false),
+        // #[account(1, name="delegate", desc="Owner of the delegated account")]
+        AccountMeta::new_readonly(delegate.key(), false),
+        // #[account(2, writable, name="metadata", desc="Metadata account")]
+        AccountMeta::new(accounts.metadata.key(), false),
+        // #[account(3, optional, name="master_edition", desc="Master Edition account")]
+        AccountMeta::new_readonly(accounts.edition.key(), false),
+        // #[account(4, optional, writable, name="token_record", desc="Token record account")]
+        AccountMeta::new(accounts.token_record.key(), false),
+        // #[account(5, name="mint", desc="Mint of metadata")]
+        AccountMeta::new_readonly(accounts.mint.key(), false),
+        // #[account(6, optional, writable, name="token", desc="Token account of mint")]
+        AccountMeta::new(accounts.token_account.key(), false),
+        // #[account(7, signer, name="authority", desc="Update authority or token owner")]
+        AccountMeta::new_readonly(authority.key(), true),
+        // #[account(8, signer, writable, name="payer", desc="Payer")]
+        AccountMeta::new(accounts.payer.key(), true),
+        // #[account(9, name="system_program", desc="System Program")]
+        AccountMeta::new_readonly(accounts.system_program.key(), false),
+        // #[account(10, name="sysvar_instructions", desc="Instructions sysvar account")]
+        AccountMeta::new_readonly(accounts.sysvar_instructions.key(), false),
+        // #[account(11, optional, name="spl_token_program", desc="SPL Token Program")]
+        AccountMeta::new_readonly(accounts.token_program.key(), false),
+        // #[account(12, optional, name="authorization_rules_program", desc="Token Authorization Rules Program")]
+        AccountMeta::new_readonly(accounts.authorization_rules_program.key(), false),
+        // #[account(13, optional, name="authorization_rules", desc="Token Authorization Rules account")]
+        AccountMeta::new_readonly(accounts.authorization_rules.key(), false),
+    ]
+}
+
+fn delegate_account_infos<'info>(
+    accounts: &PNFTAccounts<'info>,
+    delegate: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+) -> Vec<AccountInfo<'info>> {
+    vec![
+        accounts.token_metadata_program.clone(),
+        delegate.clone(),
+        accounts.metadata.clone(),
+        accounts.edition.clone(),
+        accounts.token_record.clone(),
+        accounts.mint.clone(),
+        accounts.token_account.clone(),
+        authority.clone(),
+        accounts.payer.clone(),
+        accounts.system_program.clone(),
+        accounts.sysvar_instructions.clone(),
+        accounts.token_program.clone(),
+        accounts.authorization_rules_program.clone(),
+        accounts.authorization_rules.clone(),
+    ]
+}
+
+fn lock_account_metas(accounts: &PNFTAccounts, authority: &AccountInfo) -> Vec<AccountMeta> {
+    vec![
+        // #[account(0, signer, name="authority", desc="Delegate or freeze authority")]
+        AccountMeta::new_readonly(authority.key(), true),
+        // #[account(1, optional, name="token_owner", desc="Token owner account")]
+        AccountMeta::new_readonly(accounts.token_owner.key(), false),
+        // #[account(2, writable, name="token", desc="Token account")]
+        AccountMeta::new(accounts.token_account.key(), false),
+        // #[account(3, name="mint", desc="Mint account")]
+        AccountMeta::new_readonly(accounts.mint.key(), false),
+        // #[account(4, writable, name="metadata", desc="Metadata account")]
+        AccountMeta::new(accounts.metadata.key(), false),
+        // #[account(5, optional, name="edition", desc="Edition account")]
+        AccountMeta::new_readonly(accounts.edition.key(), false),
+        // #[account(6, optional, writable, name="token_record", desc="Token record account")]
+        AccountMeta::new(accounts.token_record.key(), false),
+        // #[account(7, signer, writable, name="payer", desc="Payer")]
+        AccountMeta::new(accounts.payer.key(), true),
+        // #[account(8, name="system_program", desc="System program")]
+        AccountMeta::new_readonly(accounts.system_program.key(), false),
+        // #[account(9, name="sysvar_instructions", desc="Instructions sysvar account")]
+        AccountMeta::new_readonly(accounts.sysvar_instructions.key(), false),
+        // #[account(10, optional, name="spl_token_program", desc="SPL Token Program")]
+        AccountMeta::new_readonly(accounts.token_program.key(), false),
+        // #[account(11, optional, name="authorization_rules_program", desc="Token Authorization Rules Program")]
+        AccountMeta::new_readonly(accounts.authorization_rules_program.key(), false),
+        // #[account(12, optional, name="authorization_rules", desc="Token Authorization Rules account")]
+        AccountMeta::new_readonly(accounts.authorization_rules.key(), false),
+    ]
+}
+
+fn lock_account_infos<'info>(
+    accounts: &PNFTAccounts<'info>,
+    authority: &AccountInfo<'info>,
+) -> Vec<AccountInfo<'info>> {
+    vec![
+        authority.clone(),
+        accounts.token_owner.clone(),
+        accounts.token_account.clone(),
+        accounts.mint.clone(),
+        accounts.metadata.clone(),
+        accounts.edition.clone(),
+        accounts.token_record.clone(),
+        accounts.payer.clone(),
+        accounts.system_program.clone(),
+        accounts.sysvar_instructions.clone(),
+        accounts.token_program.clone(),
+        accounts.authorization_rules_program.clone(),
+        accounts.authorization_rules.clone(),
+    ]
+}
@@ -0,0 +1,439 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::errors::ErrorCode;
+use crate::pnft::invoke_transfer_pnft;
+use crate::pnft::PNFTTransferAccounts;
+use crate::state::DreamerState;
+use crate::state::SwappedState;
+use crate::state_config::StateConfig;
+use crate::state_config::STATE_CONFIG_SIZE;
+
+pub const DEPOSIT_RECORD_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 8 + 1 + (1 + 32);
+/// Byte offsets for filtering deposit records of a state with `memcmp`.
+pub const DEPOSIT_RECORD_STATE_OFFSET: usize = 8 + 1;
+pub const DEPOSIT_RECORD_HELD_OFFSET: usize = 8 + 1 + 32 + 32 + 32 + 8;
+
+/// Last deposit of `mint` into a dreamer or swapped PDA. The records of a state
+/// with `held` set are its inventory, counted in `StateConfig::held_assets`;
+/// deposits set it and every handler moving the asset out of the state clears
+/// it. Assets escrowed by a raffle, auction or listing of the state stay held.
+#[account]
+pub struct DepositRecord {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub mint: Pubkey,
+    pub depositor: Pubkey,
+    pub deposited_at: i64,
+    /// The state still holds `mint`
+    pub held: bool,
+    /// Destination other than the depositor approved by the state authority
+    pub approved_destination: Option<Pubkey>,
+}
+
+#[event]
+pub struct AssetDeposited {
+    pub state: Pubkey,
+    pub mint: Pubkey,
+    pub depositor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct DepositTokenDreamer<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated)]
+    pub pda_dreamer: Account<'info, DreamerState>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut, constraint =
+        token_source.owner == user.key() && token_source.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub token_source: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = pda_dreamer,
+    )]
+    pub token_destination: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = DEPOSIT_RECORD_SIZE,
+        seeds = [b"deposit", pda_dreamer.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub deposit_record: Account<'info, DepositRecord>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_dreamer.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Account<'info, StateConfig>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositTokenSwap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(constraint = pda_swapped.version.is_current() @ ErrorCode::StateNotMigrated)]
+    pub pda_swapped: Account<'info, SwappedState>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut, constraint =
+        token_source.owner == user.key() && token_source.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub token_source: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = pda_swapped,
+    )]
+    pub token_destination: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = DEPOSIT_RECORD_SIZE,
+        seeds = [b"deposit", pda_swapped.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub deposit_record: Account<'info, DepositRecord>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_swapped.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Account<'info, StateConfig>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositPNFTDreamer<'info> {
+    #[account(mut)]
+    user: Signer<'info>,
+    #[account(constraint = owner.version.is_current() @ ErrorCode::StateNotMigrated)]
+    owner: Box<Account<'info, DreamerState>>,
+    mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        token_account.owner == user.key() && token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because its address is the owner's associated token account
+    #[account(mut, address = get_associated_token_address(&owner.key(), &mint.key()))]
+    destination_token_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = DEPOSIT_RECORD_SIZE,
+        seeds = [b"deposit", owner.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    deposit_record: Box<Account<'info, DepositRecord>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", owner.key().as_ref()],
+        bump,
+    )]
+    state_config: Box<Account<'info, StateConfig>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    destination_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    sysvar_instructions: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    authorization_rules_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositPNFTSwap<'info> {
+    #[account(mut)]
+    user: Signer<'info>,
+    #[account(constraint = owner.version.is_current() @ ErrorCode::StateNotMigrated)]
+    owner: Box<Account<'info, SwappedState>>,
+    mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        token_account.owner == user.key() && token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because its address is the owner's associated token account
+    #[account(mut, address = get_associated_token_address(&owner.key(), &mint.key()))]
+    destination_token_account: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = DEPOSIT_RECORD_SIZE,
+        seeds = [b"deposit", owner.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    deposit_record: Box<Account<'info, DepositRecord>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", owner.key().as_ref()],
+        bump,
+    )]
+    state_config: Box<Account<'info, StateConfig>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    destination_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    sysvar_instructions: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    authorization_rules_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+/// Records a deposit of `mint` into `state` and counts it in the state's
+/// `held_assets` unless the record was already held. Topping up a held record
+/// keeps its depositor and approval; a new depositor clears the approval given
+/// for the previous one.
+pub(crate) fn record_deposit(
+    deposit_record: &mut DepositRecord,
+    bump: u8,
+    state_config: &mut Account<StateConfig>,
+    state_config_bump: u8,
+    state: Pubkey,
+    mint: Pubkey,
+    depositor: Pubkey,
+) -> Result<()> {
+    let timestamp = Clock::get()?.unix_timestamp;
+    state_config.bump = state_config_bump;
+    state_config.state = state;
+    if !deposit_record.held {
+        state_config.held_assets = state_config.held_assets.saturating_add(1);
+    }
+
+    deposit_record.bump = bump;
+    deposit_record.state = state;
+    deposit_record.mint = mint;
+    if !deposit_record.held {
+        if deposit_record.depositor != Pubkey::default() && deposit_record.depositor != depositor {
+            deposit_record.approved_destination = None;
+        }
+        deposit_record.depositor = depositor;
+    }
+    deposit_record.deposited_at = timestamp;
+    deposit_record.held = true;
+
+    emit!(AssetDeposited {
+        state,
+        mint,
+        depositor,
+        timestamp,
+    });
+    Ok(())
+}
+
+/// Clears `held` on the deposit record of a withdrawn asset, returning whether
+/// it was set. Assets that were never deposited through this program have no
+/// record and are skipped.
+pub(crate) fn clear_held(deposit_record: &AccountInfo) -> Result<bool> {
+    if *deposit_record.owner != crate::id() || deposit_record.data_is_empty() {
+        return Ok(false);
+    }
+    let mut data = deposit_record.try_borrow_mut_data()?;
+    let mut record = DepositRecord::try_deserialize(&mut &data[..])?;
+    if !record.held {
+        return Ok(false);
+    }
+    record.held = false;
+    record.try_serialize(&mut &mut data[..])?;
+    Ok(true)
+}
+
+/// Clears `held` on the deposit record of a withdrawn asset and takes it off
+/// the `held_assets` count in `state_config`.
+pub fn record_withdrawal(deposit_record: &AccountInfo, state_config: &AccountInfo) -> Result<()> {
+    if !clear_held(deposit_record)? {
+        return Ok(());
+    }
+    let mut config = match StateConfig::load(state_config)? {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+    config.held_assets = config.held_assets.saturating_sub(1);
+    config.try_serialize(&mut &mut state_config.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+fn transfer_nft_from_user<'info>(
+    token_program: AccountInfo<'info>,
+    token_source: AccountInfo<'info>,
+    token_destination: AccountInfo<'info>,
+    user: AccountInfo<'info>,
+) -> Result<()> {
+    invoke(
+        &spl_token::instruction::transfer(
+            &token_program.key(),
+            &token_source.key(),
+            &token_destination.key(),
+            &user.key(),
+            &[],
+            1,
+        )?,
+        &[token_program, token_source, token_destination, user],
+    )?;
+    Ok(())
+}
+
+pub fn deposit_nft_dreamer(ctx: Context<DepositTokenDreamer>) -> Result<()> {
+    msg!(format!("Depositing NFT To Dreamer Account").as_str());
+
+    transfer_nft_from_user(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.token_source.to_account_info(),
+        ctx.accounts.token_destination.to_account_info(),
+        ctx.accounts.user.to_account_info(),
+    )?;
+    record_deposit(
+        &mut ctx.accounts.deposit_record,
+        *ctx.bumps.get("deposit_record").unwrap(),
+        &mut ctx.accounts.state_config,
+        *ctx.bumps.get("state_config").unwrap(),
+        ctx.accounts.pda_dreamer.key(),
+        ctx.accounts.mint.key(),
+        ctx.accounts.user.key(),
+    )
+}
+
+pub fn deposit_nft_swap(ctx: Context<DepositTokenSwap>) -> Result<()> {
+    msg!(format!("Depositing NFT To Swap Account").as_str());
+
+    transfer_nft_from_user(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.token_source.to_account_info(),
+        ctx.accounts.token_destination.to_account_info(),
+        ctx.accounts.user.to_account_info(),
+    )?;
+    record_deposit(
+        &mut ctx.accounts.deposit_record,
+        *ctx.bumps.get("deposit_record").unwrap(),
+        &mut ctx.accounts.state_config,
+        *ctx.bumps.get("state_config").unwrap(),
+        ctx.accounts.pda_swapped.key(),
+        ctx.accounts.mint.key(),
+        ctx.accounts.user.key(),
+    )
+}
+
+pub fn deposit_pnft_dreamer(ctx: Context<DepositPNFTDreamer>) -> Result<()> {
+    msg!(format!("Depositing pNFT To Dreamer Account").as_str());
+
+    invoke_transfer_pnft(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.token_account.to_account_info(),
+            token_owner: ctx.accounts.user.to_account_info(),
+            destination_token_account: ctx.accounts.destination_token_account.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.token_record.to_account_info(),
+            destination_token_record: ctx.accounts.destination_token_record.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+            payer: ctx.accounts.user.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[],
+    )?;
+    record_deposit(
+        &mut ctx.accounts.deposit_record,
+        *ctx.bumps.get("deposit_record").unwrap(),
+        &mut ctx.accounts.state_config,
+        *ctx.bumps.get("state_config").unwrap(),
+        ctx.accounts.owner.key(),
+        ctx.accounts.mint.key(),
+        ctx.accounts.user.key(),
+    )
+}
+
+pub fn deposit_pnft_swap(ctx: Context<DepositPNFTSwap>) -> Result<()> {
+    msg!(format!("Depositing pNFT To Swap Account").as_str());
+
+    invoke_transfer_pnft(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.token_account.to_account_info(),
+            token_owner: ctx.accounts.user.to_account_info(),
+            destination_token_account: ctx.accounts.destination_token_account.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.token_record.to_account_info(),
+            destination_token_record: ctx.accounts.destination_token_record.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+            payer: ctx.accounts.user.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[],
+    )?;
+    record_deposit(
+        &mut ctx.accounts.deposit_record,
+        *ctx.bumps.get("deposit_record").unwrap(),
+        &mut ctx.accounts.state_config,
+        *ctx.bumps.get("state_config").unwrap(),
+        ctx.accounts.owner.key(),
+        ctx.accounts.mint.key(),
+        ctx.accounts.user.key(),
+    )
+}
@@ -73,6 +73,8 @@ pub enum ErrorCode {
     InvalidStakeEntry,
     #[msg("Cannot update unstaked entry")]
     CannotUpdateUnstakedEntry,
+    #[msg("Token is locked")]
+    TokenLocked,
 
     // boost errors
     #[msg("Cannot boost unstaked token")]
@@ -87,6 +89,8 @@ pub enum ErrorCode {
     InvalidPaymentManager,
     #[msg("Cannot boost a fungible token stake entry")]
     CannotBoostFungibleToken,
+    #[msg("Invalid boost payment amount")]
+    InvalidBoostPaymentAmount,
 
     // group errors
     #[msg("Grouped stake entry")]
@@ -99,6 +103,8 @@ pub enum ErrorCode {
     ActiveGroupEntry,
     #[msg("Stake entry not found in group")]
     StakeEntryNotFoundInGroup,
+    #[msg("Group stake entry is full")]
+    GroupStakeEntryFull,
 
     // claim stake entry fundssl
     #[msg("Invalid funds mint")]
@@ -107,4 +113,142 @@ pub enum ErrorCode {
     InvalidMintForTokenAccount,
     #[msg("Stake entry funds token account is empty")]
     StakeEntryFundsTokenAccountEmpty,
+
+    // royalty errors
+    #[msg("Invalid royalty amount")]
+    InvalidRoyaltyAmount = 140,
+    #[msg("Missing royalty payment accounts")]
+    MissingRoyaltyAccounts,
+    #[msg("Invalid royalty payment token account")]
+    InvalidRoyaltyPaymentTokenAccount,
+    #[msg("Invalid royalty creator account")]
+    InvalidRoyaltyCreator,
+
+    // reward errors
+    #[msg("Invalid reward distributor")]
+    InvalidRewardDistributor = 150,
+    #[msg("Invalid reward entry")]
+    InvalidRewardEntry,
+    #[msg("Invalid reward distributor token account")]
+    InvalidRewardDistributorTokenAccount,
+    #[msg("Invalid reward mint")]
+    InvalidRewardMint,
+    #[msg("Reward distributor has insufficient rewards")]
+    InsufficientRewards,
+    #[msg("Reward amount overflow")]
+    RewardAmountOverflow,
+
+    // vault errors
+    #[msg("Destination is not the depositor or an approved destination")]
+    InvalidWithdrawDestination = 160,
+    #[msg("Invalid allowlist proof")]
+    InvalidAllowlistProof,
+    #[msg("Invalid allowlist claim marker")]
+    InvalidAllowlistClaimMarker,
+    #[msg("Destination already claimed in this allowlist round")]
+    AllowlistAlreadyClaimed,
+    #[msg("No reward claims are open")]
+    RewardClaimsClosed,
+    #[msg("Invalid reward claim proof")]
+    InvalidRewardClaimProof,
+    #[msg("Transfer rate limit exceeded")]
+    RateLimitExceeded,
+    #[msg("Invalid rate limit")]
+    InvalidRateLimit,
+
+    // raffle errors
+    #[msg("Invalid raffle")]
+    InvalidRaffle = 170,
+    #[msg("Raffle has ended")]
+    RaffleEnded,
+    #[msg("Raffle has not ended")]
+    RaffleNotEnded,
+    #[msg("Raffle is sold out")]
+    RaffleSoldOut,
+    #[msg("Raffle winner already drawn")]
+    RaffleAlreadyDrawn,
+    #[msg("Raffle winner not drawn")]
+    RaffleNotDrawn,
+    #[msg("Raffle already settled")]
+    RaffleAlreadySettled,
+    #[msg("Invalid raffle ticket")]
+    InvalidRaffleTicket,
+    #[msg("Cannot close raffle with unsettled tickets")]
+    CannotCloseRaffleWithTickets,
+    #[msg("Invalid randomness account")]
+    InvalidRandomnessAccount,
+    #[msg("Randomness has not been revealed")]
+    RandomnessNotRevealed,
+    #[msg("Raffle draw already requested")]
+    RaffleDrawRequested,
+
+    // auction errors
+    #[msg("Invalid auction")]
+    InvalidAuction = 200,
+    #[msg("Auction has ended")]
+    AuctionEnded,
+    #[msg("Auction has not ended")]
+    AuctionNotEnded,
+    #[msg("Bid is below the reserve price or minimum increment")]
+    BidTooLow,
+    #[msg("Invalid previous bidder")]
+    InvalidPreviousBidder,
+
+    // listing errors
+    #[msg("Invalid listing")]
+    InvalidListing = 220,
+    #[msg("Listing price does not cover royalties and fees")]
+    InvalidListingPrice,
+    #[msg("Invalid listing fee")]
+    InvalidListingFee,
+    #[msg("Invalid payment token account")]
+    InvalidPaymentTokenAccount,
+
+    // collection bid errors
+    #[msg("Invalid collection bid")]
+    InvalidCollectionBid = 240,
+    #[msg("Mint is not a verified member of the bid collection")]
+    InvalidCollectionBidMint,
+
+    // rental errors
+    #[msg("Invalid rental")]
+    InvalidRental = 260,
+    #[msg("Asset is already rented")]
+    RentalUnavailable,
+    #[msg("Rental has not expired")]
+    RentalNotExpired,
+    #[msg("Invalid rental duration")]
+    InvalidRentalDuration,
+
+    // loan errors
+    #[msg("Invalid loan offer")]
+    InvalidLoanOffer = 280,
+    #[msg("Invalid loan")]
+    InvalidLoan,
+    #[msg("Mint is not valid collateral for this offer")]
+    InvalidLoanCollateral,
+    #[msg("Loan is past its deadline")]
+    LoanExpired,
+    #[msg("Loan has not defaulted")]
+    LoanNotExpired,
+
+    // vesting errors
+    #[msg("Invalid vesting schedule")]
+    InvalidVestingSchedule = 300,
+    #[msg("Invalid vesting mint")]
+    InvalidVestingMint,
+    #[msg("Nothing has vested yet")]
+    NothingVested,
+    #[msg("Vesting schedule has vested amounts left to claim")]
+    VestedNotClaimed,
+
+    // state errors
+    #[msg("Cannot close state while it holds assets")]
+    CannotCloseStateWithAssets = 320,
+    #[msg("Invalid state token account")]
+    InvalidStateTokenAccount,
+    #[msg("Cannot close state with open raffles, auctions, listings or vesting schedules")]
+    CannotCloseStateWithPositions,
+    #[msg("State account uses an old layout, run migrate_state")]
+    StateNotMigrated,
 }
\ No newline at end of file
@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::stake_entry::StakeEntry;
+use crate::stake_pool::StakePool;
+
+pub const GROUP_STAKE_ENTRY_MAX_ENTRIES: usize = 10;
+/// Groups smaller than this do not earn the group multiplier
+pub const GROUP_STAKE_ENTRY_MIN_ENTRIES: usize = 2;
+pub const GROUP_STAKE_ENTRY_SIZE: usize =
+    8 + 1 + 8 + 32 + 32 + (4 + 32 * GROUP_STAKE_ENTRY_MAX_ENTRIES) + 8 + 4;
+
+#[account]
+pub struct GroupStakeEntry {
+    pub bump: u8,
+    pub identifier: u64,
+    pub authority: Pubkey,
+    pub stake_pool: Pubkey,
+    pub stake_entries: Vec<Pubkey>,
+    /// Last time an entry was added or removed
+    pub changed_at: i64,
+    /// Copied from the stake pool when the group is created
+    pub min_group_seconds: u32,
+}
+
+#[derive(Accounts)]
+#[instruction(identifier: u64)]
+pub struct InitGroup<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = GROUP_STAKE_ENTRY_SIZE,
+        seeds = [b"group_stake_entry", authority.key().as_ref(), &identifier.to_le_bytes()],
+        bump,
+    )]
+    pub group_stake_entry: Box<Account<'info, GroupStakeEntry>>,
+    pub stake_pool: Box<Account<'info, StakePool>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGroup<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::InvalidAuthority)]
+    pub group_stake_entry: Box<Account<'info, GroupStakeEntry>>,
+    #[account(mut,
+        constraint = stake_entry.pool == group_stake_entry.stake_pool @ ErrorCode::InvalidStakePool,
+        constraint = stake_entry.last_staker == authority.key() @ ErrorCode::InvalidLastStaker)]
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGroup<'info> {
+    #[account(
+        mut,
+        close = authority,
+        has_one = authority @ ErrorCode::InvalidAuthority,
+        constraint = group_stake_entry.stake_entries.is_empty() @ ErrorCode::ActiveGroupEntry,
+    )]
+    pub group_stake_entry: Box<Account<'info, GroupStakeEntry>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub fn init_group(ctx: Context<InitGroup>, identifier: u64) -> Result<()> {
+    let group_stake_entry = &mut ctx.accounts.group_stake_entry;
+    group_stake_entry.bump = *ctx.bumps.get("group_stake_entry").unwrap();
+    group_stake_entry.identifier = identifier;
+    group_stake_entry.authority = ctx.accounts.authority.key();
+    group_stake_entry.stake_pool = ctx.accounts.stake_pool.key();
+    group_stake_entry.stake_entries = Vec::new();
+    group_stake_entry.changed_at = Clock::get()?.unix_timestamp;
+    group_stake_entry.min_group_seconds = ctx.accounts.stake_pool.min_group_seconds;
+
+    Ok(())
+}
+
+pub fn add_to_group(ctx: Context<UpdateGroup>) -> Result<()> {
+    let group_stake_entry = &mut ctx.accounts.group_stake_entry;
+    let stake_entry = &mut ctx.accounts.stake_entry;
+
+    require!(stake_entry.grouping_info.is_none(), ErrorCode::GroupedStakeEntry);
+    require!(
+        group_stake_entry.stake_entries.len() < GROUP_STAKE_ENTRY_MAX_ENTRIES,
+        ErrorCode::GroupStakeEntryFull
+    );
+
+    group_stake_entry.stake_entries.push(stake_entry.key());
+    group_stake_entry.changed_at = Clock::get()?.unix_timestamp;
+    stake_entry.grouping_info = Some(group_stake_entry.key());
+
+    Ok(())
+}
+
+pub fn remove_from_group(ctx: Context<UpdateGroup>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let group_stake_entry = &mut ctx.accounts.group_stake_entry;
+    let stake_entry = &mut ctx.accounts.stake_entry;
+
+    require!(
+        stake_entry.grouping_info == Some(group_stake_entry.key()),
+        ErrorCode::UngroupedStakeEntry
+    );
+    require!(
+        now - group_stake_entry.changed_at >= group_stake_entry.min_group_seconds as i64,
+        ErrorCode::MinGroupSecondsNotSatisfied
+    );
+    let index = group_stake_entry
+        .stake_entries
+        .iter()
+        .position(|key| *key == stake_entry.key())
+        .ok_or(ErrorCode::StakeEntryNotFoundInGroup)?;
+
+    group_stake_entry.stake_entries.remove(index);
+    group_stake_entry.changed_at = now;
+    stake_entry.grouping_info = None;
+
+    Ok(())
+}
+
+pub fn close_group(_ctx: Context<CloseGroup>) -> Result<()> {
+    Ok(())
+}
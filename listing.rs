@@ -0,0 +1,571 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::deposit::record_deposit;
+use crate::deposit::record_withdrawal;
+use crate::deposit::DepositRecord;
+use crate::deposit::DEPOSIT_RECORD_SIZE;
+use crate::errors::DreamersError;
+use crate::errors::ErrorCode;
+use crate::pnft::invoke_transfer_asset;
+use crate::pnft::PNFTTransferAccounts;
+use crate::royalty::pay_royalties;
+use crate::royalty::RoyaltyArgs;
+use crate::state::SwappedState;
+use crate::state_config::record_closed_position;
+use crate::state_config::record_open_position;
+use crate::state_config::StateConfig;
+use crate::state_config::STATE_CONFIG_SIZE;
+use crate::utils::assert_metadata_for_mint;
+use crate::utils::close_escrow_token_account;
+
+pub const LISTING_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 8 + (1 + 32) + 2;
+
+/// Fixed-price sale on a swap PDA. The asset is escrowed in a token account
+/// owned by the listing, out of reach of the swap authority, and recorded as a
+/// deposit of the swap PDA while it is listed.
+#[account]
+pub struct Listing {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,
+    /// Mint of the payment, `None` when paying in SOL
+    pub payment_mint: Option<Pubkey>,
+    /// Swap authority fee at listing time, later fee changes do not apply
+    pub listing_fee_bps: u16,
+}
+
+impl Listing {
+    /// Signer seeds of the listing PDA, which owns the asset escrow
+    pub fn seeds(&self) -> [&[u8]; 4] {
+        [
+            b"listing",
+            self.state.as_ref(),
+            self.mint.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
+
+#[event]
+pub struct AssetListed {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub payment_mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct AssetSold {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub royalty: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct AssetDelisted {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct SetListingFee<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = pda_swapped.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_swapped: Account<'info, SwappedState>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_swapped.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Account<'info, StateConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ListAsset<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = pda_swapped.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_swapped: Box<Account<'info, SwappedState>>,
+    #[account(
+        init,
+        payer = seller,
+        space = LISTING_SIZE,
+        seeds = [b"listing", pda_swapped.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        seller_token_account.owner == seller.key() && seller_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub seller_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = listing,
+    )]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = DEPOSIT_RECORD_SIZE,
+        seeds = [b"deposit", pda_swapped.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub deposit_record: Box<Account<'info, DepositRecord>>,
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_swapped.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Box<Account<'info, StateConfig>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub seller_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyAsset<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        constraint = pda_swapped.key() == listing.state @ ErrorCode::InvalidListing,
+        constraint = pda_swapped.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_swapped: Box<Account<'info, SwappedState>>,
+    /// CHECK: This is not dangerous because we only update it in record_withdrawal and record_closed_position
+    #[account(mut, seeds = [b"state_config", pda_swapped.key().as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller @ ErrorCode::InvalidListing,
+        has_one = mint @ ErrorCode::InvalidListing,
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+    /// CHECK: This is not dangerous because it must match the listing seller
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because it must be the swap authority
+    #[account(mut, address = pda_swapped.authority @ ErrorCode::InvalidListing)]
+    pub fee_destination: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because it is only used for SPL payments and validated in transfer_payment
+    #[account(mut)]
+    pub buyer_payment_token_account: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because it is only used for SPL payments and validated in transfer_payment
+    #[account(mut)]
+    pub seller_payment_token_account: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because it is only used for SPL payments and validated in transfer_payment
+    #[account(mut)]
+    pub fee_payment_token_account: UncheckedAccount<'info>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        escrow_token_account.owner == listing.key() && escrow_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we only clear its held flag in record_withdrawal
+    #[account(mut, seeds = [b"deposit", pda_swapped.key().as_ref(), mint.key().as_ref()], bump)]
+    pub deposit_record: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub buyer_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in assert_metadata_for_mint
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DelistAsset<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(
+        constraint = pda_swapped.key() == listing.state @ ErrorCode::InvalidListing,
+        constraint = pda_swapped.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_swapped: Box<Account<'info, SwappedState>>,
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller @ ErrorCode::InvalidListing,
+        has_one = mint @ ErrorCode::InvalidListing,
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+    /// CHECK: This is not dangerous because we only update it in record_withdrawal and record_closed_position
+    #[account(mut, seeds = [b"state_config", pda_swapped.key().as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        escrow_token_account.owner == listing.key() && escrow_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we only clear its held flag in record_withdrawal
+    #[account(mut, seeds = [b"deposit", pda_swapped.key().as_ref(), mint.key().as_ref()], bump)]
+    pub deposit_record: UncheckedAccount<'info>,
+    #[account(mut, constraint =
+        seller_token_account.owner == seller.key() && seller_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub seller_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub seller_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Sends `amount` from `payer` to `recipient`, in lamports when `payment_mint` is
+/// `None` and otherwise between their token accounts for `payment_mint`.
+#[allow(clippy::too_many_arguments)]
+fn transfer_payment<'info>(
+    payment_mint: Option<Pubkey>,
+    amount: u64,
+    payer: &AccountInfo<'info>,
+    payer_token_account: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    recipient_token_account: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    match payment_mint {
+        Some(payment_mint) => {
+            let source = Account::<TokenAccount>::try_from(payer_token_account)?;
+            let destination = Account::<TokenAccount>::try_from(recipient_token_account)?;
+            require!(
+                source.mint == payment_mint
+                    && source.owner == payer.key()
+                    && destination.mint == payment_mint
+                    && destination.owner == recipient.key(),
+                ErrorCode::InvalidPaymentTokenAccount
+            );
+            invoke(
+                &spl_token::instruction::transfer(
+                    &token_program.key(),
+                    &payer_token_account.key(),
+                    &recipient_token_account.key(),
+                    &payer.key(),
+                    &[],
+                    amount,
+                )?,
+                &[
+                    token_program.clone(),
+                    payer_token_account.clone(),
+                    recipient_token_account.clone(),
+                    payer.clone(),
+                ],
+            )?;
+        }
+        None => {
+            invoke(
+                &system_instruction::transfer(&payer.key(), &recipient.key(), amount),
+                &[payer.clone(), recipient.clone(), system_program.clone()],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+pub fn set_listing_fee(ctx: Context<SetListingFee>, listing_fee_bps: u16) -> Result<()> {
+    require!(listing_fee_bps <= 10_000, ErrorCode::InvalidListingFee);
+
+    let state_config = &mut ctx.accounts.state_config;
+    state_config.bump = *ctx.bumps.get("state_config").unwrap();
+    state_config.state = ctx.accounts.pda_swapped.key();
+    state_config.listing_fee_bps = listing_fee_bps;
+    Ok(())
+}
+
+/// Escrows the seller's asset for sale on a swap PDA. The swap authority co-signs
+/// since the listing counts against the state's held assets and open positions.
+pub fn list_asset(
+    ctx: Context<ListAsset>,
+    price: u64,
+    payment_mint: Option<Pubkey>,
+) -> Result<()> {
+    msg!(format!("Listing Asset To Swap Account").as_str());
+
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.seller_token_account.to_account_info(),
+            token_owner: ctx.accounts.seller.to_account_info(),
+            destination_token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: ctx.accounts.listing.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.seller_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.escrow_token_record.to_account_info(),
+            authority: ctx.accounts.seller.to_account_info(),
+            payer: ctx.accounts.seller.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[],
+    )?;
+
+    record_deposit(
+        &mut ctx.accounts.deposit_record,
+        *ctx.bumps.get("deposit_record").unwrap(),
+        &mut ctx.accounts.state_config,
+        *ctx.bumps.get("state_config").unwrap(),
+        ctx.accounts.pda_swapped.key(),
+        ctx.accounts.mint.key(),
+        ctx.accounts.seller.key(),
+    )?;
+    record_open_position(
+        &mut ctx.accounts.state_config,
+        *ctx.bumps.get("state_config").unwrap(),
+        ctx.accounts.pda_swapped.key(),
+    );
+
+    let listing = &mut ctx.accounts.listing;
+    listing.bump = *ctx.bumps.get("listing").unwrap();
+    listing.state = ctx.accounts.pda_swapped.key();
+    listing.seller = ctx.accounts.seller.key();
+    listing.mint = ctx.accounts.mint.key();
+    listing.price = price;
+    listing.payment_mint = payment_mint;
+    listing.listing_fee_bps = ctx.accounts.state_config.listing_fee_bps;
+
+    emit!(AssetListed {
+        listing: listing.key(),
+        seller: listing.seller,
+        mint: listing.mint,
+        price,
+        payment_mint,
+    });
+    Ok(())
+}
+
+/// Buys a listing at its price. The buyer pays creator royalties, the swap
+/// authority's fee and the rest to the seller. Royalty accounts are passed as
+/// remaining accounts in the order `pay_royalties` expects.
+pub fn buy_asset<'info>(ctx: Context<'_, '_, '_, 'info, BuyAsset<'info>>) -> Result<()> {
+    msg!(format!("Buying Asset From Swap Account").as_str());
+
+    let listing = &ctx.accounts.listing;
+    let metadata = assert_metadata_for_mint(&ctx.accounts.metadata, &listing.mint)?;
+    let royalty = pay_royalties(
+        &RoyaltyArgs {
+            price: listing.price,
+            payment_mint: listing.payment_mint,
+        },
+        &metadata,
+        &ctx.accounts.buyer.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        ctx.remaining_accounts,
+    )?;
+
+    let fee = (listing.price as u128 * listing.listing_fee_bps as u128 / 10_000) as u64;
+    let seller_amount = listing
+        .price
+        .checked_sub(royalty)
+        .and_then(|amount| amount.checked_sub(fee))
+        .ok_or(ErrorCode::InvalidListingPrice)?;
+
+    transfer_payment(
+        listing.payment_mint,
+        fee,
+        &ctx.accounts.buyer.to_account_info(),
+        &ctx.accounts.buyer_payment_token_account,
+        &ctx.accounts.fee_destination,
+        &ctx.accounts.fee_payment_token_account,
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    transfer_payment(
+        listing.payment_mint,
+        seller_amount,
+        &ctx.accounts.buyer.to_account_info(),
+        &ctx.accounts.buyer_payment_token_account,
+        &ctx.accounts.seller,
+        &ctx.accounts.seller_payment_token_account,
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            token_owner: listing.to_account_info(),
+            destination_token_account: ctx.accounts.buyer_token_account.to_account_info(),
+            destination: ctx.accounts.buyer.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.escrow_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.buyer_token_record.to_account_info(),
+            authority: listing.to_account_info(),
+            payer: ctx.accounts.buyer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[&listing.seeds()],
+    )?;
+    close_escrow_token_account(
+        &ctx.accounts.escrow_token_account.to_account_info(),
+        &ctx.accounts.seller.to_account_info(),
+        &listing.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &[&listing.seeds()],
+    )?;
+    record_withdrawal(&ctx.accounts.deposit_record, &ctx.accounts.state_config)?;
+    record_closed_position(&ctx.accounts.state_config)?;
+
+    emit!(AssetSold {
+        listing: listing.key(),
+        seller: listing.seller,
+        buyer: ctx.accounts.buyer.key(),
+        mint: listing.mint,
+        price: listing.price,
+        royalty,
+        fee,
+    });
+    Ok(())
+}
+
+pub fn delist_asset(ctx: Context<DelistAsset>) -> Result<()> {
+    msg!(format!("Delisting Asset From Swap Account").as_str());
+
+    let listing = &ctx.accounts.listing;
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            token_owner: listing.to_account_info(),
+            destination_token_account: ctx.accounts.seller_token_account.to_account_info(),
+            destination: ctx.accounts.seller.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.escrow_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.seller_token_record.to_account_info(),
+            authority: listing.to_account_info(),
+            payer: ctx.accounts.seller.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[&listing.seeds()],
+    )?;
+    close_escrow_token_account(
+        &ctx.accounts.escrow_token_account.to_account_info(),
+        &ctx.accounts.seller.to_account_info(),
+        &listing.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &[&listing.seeds()],
+    )?;
+    record_withdrawal(&ctx.accounts.deposit_record, &ctx.accounts.state_config)?;
+    record_closed_position(&ctx.accounts.state_config)?;
+
+    emit!(AssetDelisted {
+        listing: listing.key(),
+        seller: ctx.accounts.seller.key(),
+        mint: ctx.accounts.mint.key(),
+    });
+    Ok(())
+}
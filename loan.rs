@@ -0,0 +1,410 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::errors::ErrorCode;
+use crate::pnft::invoke_transfer_asset;
+use crate::pnft::PNFTTransferAccounts;
+use crate::utils::assert_metadata_for_mint;
+use crate::utils::close_escrow_token_account;
+use crate::utils::transfer_lamports_from_pda;
+
+pub const LOAN_OFFER_SIZE: usize = 8 + 1 + 32 + 8 + 32 + 8 + 2 + 8;
+pub const LOAN_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 8 + 2 + 8 + 8;
+pub const SECONDS_PER_YEAR: u128 = 31_536_000;
+
+/// Lender's offer of `principal` lamports, escrowed in this account, against any
+/// NFT from a verified collection.
+#[account]
+pub struct LoanOffer {
+    pub bump: u8,
+    pub lender: Pubkey,
+    pub identifier: u64,
+    pub collection: Pubkey,
+    pub principal: u64,
+    /// Yearly interest in basis points, accrued per second
+    pub interest_bps: u16,
+    pub duration_seconds: i64,
+}
+
+/// Open loan. The collateral is held in the loan PDA's token account until the
+/// borrower repays or the lender forecloses.
+#[account]
+pub struct Loan {
+    pub bump: u8,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub mint: Pubkey,
+    pub principal: u64,
+    pub interest_bps: u16,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+impl Loan {
+    /// Principal plus interest accrued from `start_time` to `now`.
+    pub fn amount_due(&self, now: i64) -> Result<u64> {
+        let elapsed = now.saturating_sub(self.start_time).max(0) as u128;
+        let interest = (self.principal as u128)
+            .checked_mul(self.interest_bps as u128)
+            .and_then(|amount| amount.checked_mul(elapsed))
+            .ok_or(ErrorCode::InvalidLoan)?
+            / (10_000 * SECONDS_PER_YEAR);
+        let interest: u64 = interest.try_into().map_err(|_| ErrorCode::InvalidLoan)?;
+        self.principal
+            .checked_add(interest)
+            .ok_or(error!(ErrorCode::InvalidLoan))
+    }
+}
+
+#[event]
+pub struct LoanStarted {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub mint: Pubkey,
+    pub principal: u64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct LoanRepaid {
+    pub loan: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct LoanForeclosed {
+    pub loan: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LoanOfferArgs {
+    pub identifier: u64,
+    pub collection: Pubkey,
+    pub principal: u64,
+    pub interest_bps: u16,
+    pub duration_seconds: i64,
+}
+
+#[derive(Accounts)]
+#[instruction(args: LoanOfferArgs)]
+pub struct CreateLoanOffer<'info> {
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    #[account(
+        init,
+        payer = lender,
+        space = LOAN_OFFER_SIZE,
+        seeds = [b"loan_offer", lender.key().as_ref(), &args.identifier.to_le_bytes()],
+        bump,
+    )]
+    pub loan_offer: Account<'info, LoanOffer>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLoanOffer<'info> {
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    #[account(mut, close = lender, has_one = lender @ ErrorCode::InvalidLoanOffer)]
+    pub loan_offer: Account<'info, LoanOffer>,
+}
+
+#[derive(Accounts)]
+pub struct TakeLoan<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    #[account(mut, close = lender, has_one = lender @ ErrorCode::InvalidLoanOffer)]
+    pub loan_offer: Box<Account<'info, LoanOffer>>,
+    /// CHECK: This is not dangerous because it must match the offer lender
+    #[account(mut)]
+    pub lender: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = borrower,
+        space = LOAN_SIZE,
+        seeds = [b"loan", mint.key().as_ref()],
+        bump,
+    )]
+    pub loan: Box<Account<'info, Loan>>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        borrower_token_account.owner == borrower.key() && borrower_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub borrower_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        associated_token::mint = mint,
+        associated_token::authority = loan,
+    )]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub borrower_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in assert_metadata_for_mint
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by `repay_loan` and `foreclose_loan`, which both release the collateral
+/// from escrow and close the loan. The signer receives the collateral.
+#[derive(Accounts)]
+pub struct CloseLoan<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        close = borrower,
+        has_one = lender @ ErrorCode::InvalidLoan,
+        has_one = borrower @ ErrorCode::InvalidLoan,
+        has_one = mint @ ErrorCode::InvalidLoan,
+    )]
+    pub loan: Box<Account<'info, Loan>>,
+    /// CHECK: This is not dangerous because it must match the loan lender
+    #[account(mut)]
+    pub lender: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because it must match the loan borrower
+    #[account(mut)]
+    pub borrower: UncheckedAccount<'info>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        escrow_token_account.owner == loan.key() && escrow_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = signer,
+    )]
+    pub recipient_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub recipient_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CloseLoan<'info> {
+    /// Moves the collateral from escrow to the signer, signed by the loan PDA, and
+    /// returns the escrow rent to the borrower who paid it.
+    fn release_collateral(&self) -> Result<()> {
+        let loan = &self.loan;
+        let seeds: &[&[u8]] = &[b"loan", loan.mint.as_ref(), &[loan.bump]];
+        invoke_transfer_asset(
+            &PNFTTransferAccounts {
+                token_account: self.escrow_token_account.to_account_info(),
+                token_owner: loan.to_account_info(),
+                destination_token_account: self.recipient_token_account.to_account_info(),
+                destination: self.signer.to_account_info(),
+                mint: self.mint.to_account_info(),
+                metadata: self.metadata.to_account_info(),
+                edition: self.edition.to_account_info(),
+                token_record: self.escrow_token_record.to_account_info(),
+                destination_token_record: self.recipient_token_record.to_account_info(),
+                authority: loan.to_account_info(),
+                payer: self.signer.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                sysvar_instructions: self.sysvar_instructions.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                associated_token_program: self.associated_token_program.to_account_info(),
+                authorization_rules_program: self.authorization_rules_program.to_account_info(),
+                authorization_rules: self.authorization_rules.to_account_info(),
+            },
+            &[seeds],
+        )?;
+        close_escrow_token_account(
+            &self.escrow_token_account.to_account_info(),
+            &self.borrower.to_account_info(),
+            &loan.to_account_info(),
+            &self.token_program.to_account_info(),
+            &[seeds],
+        )
+    }
+}
+
+pub fn create_loan_offer(ctx: Context<CreateLoanOffer>, args: LoanOfferArgs) -> Result<()> {
+    require!(
+        args.principal > 0 && args.duration_seconds > 0,
+        ErrorCode::InvalidLoanOffer
+    );
+
+    invoke(
+        &system_instruction::transfer(
+            &ctx.accounts.lender.key(),
+            &ctx.accounts.loan_offer.key(),
+            args.principal,
+        ),
+        &[
+            ctx.accounts.lender.to_account_info(),
+            ctx.accounts.loan_offer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    let loan_offer = &mut ctx.accounts.loan_offer;
+    loan_offer.bump = *ctx.bumps.get("loan_offer").unwrap();
+    loan_offer.lender = ctx.accounts.lender.key();
+    loan_offer.identifier = args.identifier;
+    loan_offer.collection = args.collection;
+    loan_offer.principal = args.principal;
+    loan_offer.interest_bps = args.interest_bps;
+    loan_offer.duration_seconds = args.duration_seconds;
+    Ok(())
+}
+
+/// Closing the offer returns the escrowed principal to the lender with the rent.
+pub fn cancel_loan_offer(_ctx: Context<CancelLoanOffer>) -> Result<()> {
+    Ok(())
+}
+
+/// Escrows the borrower's NFT in the loan PDA and pays out the offer principal.
+pub fn take_loan(ctx: Context<TakeLoan>) -> Result<()> {
+    msg!(format!("Taking Loan Against {}", ctx.accounts.mint.key()).as_str());
+
+    let loan_offer = &ctx.accounts.loan_offer;
+    let metadata = assert_metadata_for_mint(&ctx.accounts.metadata, &ctx.accounts.mint.key())?;
+    require!(
+        metadata.collection.as_ref().map_or(false, |collection| {
+            collection.verified && collection.key == loan_offer.collection
+        }),
+        ErrorCode::InvalidLoanCollateral
+    );
+
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.borrower_token_account.to_account_info(),
+            token_owner: ctx.accounts.borrower.to_account_info(),
+            destination_token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: ctx.accounts.loan.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.borrower_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.escrow_token_record.to_account_info(),
+            authority: ctx.accounts.borrower.to_account_info(),
+            payer: ctx.accounts.borrower.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[],
+    )?;
+
+    transfer_lamports_from_pda(
+        &loan_offer.to_account_info(),
+        &ctx.accounts.borrower.to_account_info(),
+        loan_offer.principal,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let loan = &mut ctx.accounts.loan;
+    loan.bump = *ctx.bumps.get("loan").unwrap();
+    loan.lender = loan_offer.lender;
+    loan.borrower = ctx.accounts.borrower.key();
+    loan.mint = ctx.accounts.mint.key();
+    loan.principal = loan_offer.principal;
+    loan.interest_bps = loan_offer.interest_bps;
+    loan.start_time = now;
+    loan.end_time = now
+        .checked_add(loan_offer.duration_seconds)
+        .ok_or(ErrorCode::InvalidLoanOffer)?;
+
+    emit!(LoanStarted {
+        loan: loan.key(),
+        lender: loan.lender,
+        borrower: loan.borrower,
+        mint: loan.mint,
+        principal: loan.principal,
+        end_time: loan.end_time,
+    });
+    Ok(())
+}
+
+/// Pays the lender principal plus interest and returns the collateral.
+pub fn repay_loan(ctx: Context<CloseLoan>) -> Result<()> {
+    msg!(format!("Repaying Loan Against {}", ctx.accounts.mint.key()).as_str());
+
+    let now = Clock::get()?.unix_timestamp;
+    let loan = &ctx.accounts.loan;
+    require_keys_eq!(ctx.accounts.signer.key(), loan.borrower, ErrorCode::InvalidLoan);
+    require!(now < loan.end_time, ErrorCode::LoanExpired);
+
+    let amount = loan.amount_due(now)?;
+    invoke(
+        &system_instruction::transfer(&ctx.accounts.signer.key(), &loan.lender, amount),
+        &[
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.lender.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+    ctx.accounts.release_collateral()?;
+
+    emit!(LoanRepaid {
+        loan: loan.key(),
+        amount,
+    });
+    Ok(())
+}
+
+/// Hands the collateral of a defaulted loan to the lender.
+pub fn foreclose_loan(ctx: Context<CloseLoan>) -> Result<()> {
+    msg!(format!("Foreclosing Loan Against {}", ctx.accounts.mint.key()).as_str());
+
+    let loan = &ctx.accounts.loan;
+    require_keys_eq!(ctx.accounts.signer.key(), loan.lender, ErrorCode::InvalidLoan);
+    require!(
+        Clock::get()?.unix_timestamp >= loan.end_time,
+        ErrorCode::LoanNotExpired
+    );
+
+    ctx.accounts.release_collateral()?;
+
+    emit!(LoanForeclosed { loan: loan.key() });
+    Ok(())
+}
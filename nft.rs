@@ -3,15 +3,20 @@ use anchor_lang::solana_program::program::invoke_signed;
 use anchor_spl::token::spl_token;
 use anchor_spl::token::TokenAccount;
 
+use crate::allowlist::assert_allowlisted_destination;
+use crate::deposit::record_withdrawal;
+use crate::errors::ErrorCode;
 use crate::state::DreamerState;
 use crate::state::SwappedState;
+use crate::state_config::assert_withdraw_destination;
+use crate::state_config::consume_rate_limit;
 // use crate::errors::DreamersError;
 
 #[derive(Accounts)]
 pub struct TransferTokenDreamer<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
-    #[account(mut)]
+    #[account(mut, constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated)]
     pub pda_dreamer: Account<'info, DreamerState>,
     #[account(mut)]
     pub token_source: Account<'info, TokenAccount>,
@@ -19,13 +24,23 @@ pub struct TransferTokenDreamer<'info> {
     pub token_destination: Account<'info, TokenAccount>,
     /// CHECK: This is not dangerous because we don't read or write from this account
     pub program_token: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in assert_withdraw_destination
+    #[account(mut, seeds = [b"state_config", pda_dreamer.key().as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in assert_withdraw_destination
+    #[account(mut, seeds = [b"deposit", pda_dreamer.key().as_ref(), token_source.mint.as_ref()], bump)]
+    pub deposit_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in assert_allowlisted_destination
+    #[account(mut)]
+    pub claim_marker: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct TransferTokenSwap<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
-    #[account(mut)]
+    #[account(mut, constraint = pda_swapped.version.is_current() @ ErrorCode::StateNotMigrated)]
     pub pda_swapped: Account<'info, SwappedState>,
     #[account(mut)]
     pub token_source: Account<'info, TokenAccount>,
@@ -33,11 +48,36 @@ pub struct TransferTokenSwap<'info> {
     pub token_destination: Account<'info, TokenAccount>,
     /// CHECK: This is not dangerous because we don't read or write from this account
     pub program_token: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we only update it in consume_rate_limit and record_withdrawal
+    #[account(mut, seeds = [b"state_config", pda_swapped.key().as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we only update it in record_withdrawal
+    #[account(mut, seeds = [b"deposit", pda_swapped.key().as_ref(), token_source.mint.as_ref()], bump)]
+    pub deposit_record: UncheckedAccount<'info>,
 }
 
-pub fn transfer_nft_dreamer(ctx: Context<TransferTokenDreamer>) -> Result<()> {
+pub fn transfer_nft_dreamer(
+    ctx: Context<TransferTokenDreamer>,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
     msg!(format!("Transferring NFT From Dreamer Account").as_str());
 
+    consume_rate_limit(&ctx.accounts.state_config)?;
+    assert_withdraw_destination(
+        &ctx.accounts.state_config,
+        &ctx.accounts.deposit_record,
+        &ctx.accounts.token_destination.owner,
+    )?;
+    assert_allowlisted_destination(
+        &ctx.accounts.pda_dreamer.key(),
+        &ctx.accounts.state_config,
+        &ctx.accounts.token_destination.owner,
+        &proof,
+        &ctx.accounts.claim_marker,
+        &ctx.accounts.user.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
     let accounts_info = vec![
         ctx.accounts.program_token.to_account_info(),
         ctx.accounts.token_source.to_account_info(),
@@ -62,6 +102,7 @@ pub fn transfer_nft_dreamer(ctx: Context<TransferTokenDreamer>) -> Result<()> {
             &[ctx.accounts.pda_dreamer.bump],
         ]],
     )?;
+    record_withdrawal(&ctx.accounts.deposit_record, &ctx.accounts.state_config)?;
 
     Ok(())
 }
@@ -69,6 +110,7 @@ pub fn transfer_nft_dreamer(ctx: Context<TransferTokenDreamer>) -> Result<()> {
 pub fn transfer_nft_swap(ctx: Context<TransferTokenSwap>) -> Result<()> {
     msg!(format!("Transferring NFT From Swap Account").as_str());
 
+    consume_rate_limit(&ctx.accounts.state_config)?;
     let accounts_info = vec![
         ctx.accounts.program_token.to_account_info(),
         ctx.accounts.token_source.to_account_info(),
@@ -92,6 +134,7 @@ pub fn transfer_nft_swap(ctx: Context<TransferTokenSwap>) -> Result<()> {
             &[ctx.accounts.pda_swapped.bump],
         ]],
     )?;
+    record_withdrawal(&ctx.accounts.deposit_record, &ctx.accounts.state_config)?;
 
     Ok(())
 }
\ No newline at end of file
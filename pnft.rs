@@ -1,21 +1,40 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token;
 use anchor_spl::token::Mint;
 use anchor_spl::token::Token;
 use anchor_spl::token::TokenAccount;
 use mpl_token_metadata::instruction::MetadataInstruction;
 use mpl_token_metadata::instruction::TransferArgs;
+use mpl_token_metadata::state::TokenStandard;
 use solana_program::instruction::Instruction;
 use solana_program::program::{invoke, invoke_signed};
 
+use crate::allowlist::assert_allowlisted_destination;
+use crate::delegate::assert_token_unlocked;
+use crate::deposit::record_withdrawal;
 use crate::errors::ErrorCode;
+use crate::royalty::pay_royalties;
+use crate::royalty::RoyaltyArgs;
 use crate::state::DreamerState;
 use crate::state::SwappedState;
+use crate::state_config::assert_withdraw_destination;
+use crate::state_config::consume_rate_limit;
+use crate::utils::assert_metadata_for_mint;
 
 #[derive(Accounts)]
 pub struct RewardPNFTDreamer<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = owner.version.is_current() @ ErrorCode::StateNotMigrated)]
     owner: Box<Account<'info, DreamerState>>,
+    /// CHECK: This is not dangerous because we validate it in assert_withdraw_destination
+    #[account(mut, seeds = [b"state_config", owner.key().as_ref()], bump)]
+    state_config: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in assert_withdraw_destination
+    #[account(mut, seeds = [b"deposit", owner.key().as_ref(), mint.key().as_ref()], bump)]
+    deposit_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in assert_allowlisted_destination
+    #[account(mut)]
+    claim_marker: UncheckedAccount<'info>,
     mint: Box<Account<'info, Mint>>,
     #[account(mut)]
     token_account: Box<Account<'info, TokenAccount>>,
@@ -54,8 +73,14 @@ pub struct RewardPNFTDreamer<'info> {
 
 #[derive(Accounts)]
 pub struct RewardPNFTSwap<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = owner.version.is_current() @ ErrorCode::StateNotMigrated)]
     owner: Box<Account<'info, SwappedState>>,
+    /// CHECK: This is not dangerous because we only update it in consume_rate_limit and record_withdrawal
+    #[account(mut, seeds = [b"state_config", owner.key().as_ref()], bump)]
+    state_config: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we only update it in record_withdrawal
+    #[account(mut, seeds = [b"deposit", owner.key().as_ref(), mint.key().as_ref()], bump)]
+    deposit_record: UncheckedAccount<'info>,
     mint: Box<Account<'info, Mint>>,
     #[account(mut)]
     token_account: Box<Account<'info, TokenAccount>>,
@@ -133,7 +158,171 @@ pub struct RewardPNFTWallet<'info> {
     system_program: Program<'info, System>,
 }
 
-pub fn transfer_pnft_dreamer(ctx: Context<RewardPNFTDreamer>) -> Result<()> {
+/// Accounts for a Token Metadata Transfer CPI issued from other instructions.
+pub struct PNFTTransferAccounts<'info> {
+    pub token_account: AccountInfo<'info>,
+    pub token_owner: AccountInfo<'info>,
+    pub destination_token_account: AccountInfo<'info>,
+    pub destination: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub edition: AccountInfo<'info>,
+    pub token_record: AccountInfo<'info>,
+    pub destination_token_record: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub authorization_rules_program: AccountInfo<'info>,
+    pub authorization_rules: AccountInfo<'info>,
+}
+
+pub fn invoke_transfer_pnft(
+    accounts: &PNFTTransferAccounts,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    assert_token_unlocked(&accounts.token_record)?;
+
+    invoke_signed(
+        &Instruction {
+            program_id: mpl_token_metadata::id(),
+            accounts: vec![
+                // #[account(0, writable, name="token", desc="Token account")]
+                AccountMeta::new(accounts.token_account.key(), false),
+                // #[account(1, name="token_owner", desc="Token account owner")]
+                AccountMeta::new_readonly(accounts.token_owner.key(), false),
+                // #[account(2, writable, name="destination", desc="Destination token account")]
+                AccountMeta::new(accounts.destination_token_account.key(), false),
+                // #[account(3, name="destination_owner", desc="Destination token account owner")]
+                AccountMeta::new_readonly(accounts.destination.key(), false),
+                // #[account(4, name="mint", desc="Mint of token asset")]
+                AccountMeta::new_readonly(accounts.mint.key(), false),
+                // #[account(5, writable, name="metadata", desc="Metadata (pda of ['metadata', program id, mint id])")]
+                AccountMeta::new(accounts.metadata.key(), false),
+                // #[account(6, optional, name="edition", desc="Edition of token asset")]
+                AccountMeta::new_readonly(accounts.edition.key(), false),
+                // #[account(7, optional, writable, name="recipient_token_record", desc="Owner token record account")]
+                AccountMeta::new(accounts.token_record.key(), false),
+                // #[account(8, optional, writable, name="destination_token_record", desc="Destination token record account")]
+                AccountMeta::new(accounts.destination_token_record.key(), false),
+                // #[account(9, signer, name="authority", desc="Transfer authority (token owner or delegate)")]
+                AccountMeta::new_readonly(accounts.authority.key(), true),
+                // #[account(10, signer, writable, name="payer", desc="Payer")]
+                AccountMeta::new(accounts.payer.key(), true),
+                // #[account(11, name="system_program", desc="System Program")]
+                AccountMeta::new_readonly(accounts.system_program.key(), false),
+                // #[account(12, name="sysvar_instructions", desc="Instructions sysvar account")]
+                AccountMeta::new_readonly(accounts.sysvar_instructions.key(), false),
+                // #[account(13, name="spl_token_program", desc="SPL Token Program")]
+                AccountMeta::new_readonly(accounts.token_program.key(), false),
+                // #[account(14, name="spl_ata_program", desc="SPL Associated Token Account program")]
+                AccountMeta::new_readonly(accounts.associated_token_program.key(), false),
+                // #[account(15, optional, name="authorization_rules_program", desc="Token Authorization Rules Program")]
+                AccountMeta::new_readonly(accounts.authorization_rules_program.key(), false),
+                // #[account(16, optional, name="authorization_rules", desc="Token Authorization Rules account")]
+                AccountMeta::new_readonly(accounts.authorization_rules.key(), false),
+            ],
+            data: MetadataInstruction::Transfer(TransferArgs::V1 {
+                amount: 1,
+                authorization_data: None,
+            })
+            .try_to_vec()
+            .unwrap(),
+        },
+        &[
+            accounts.token_account.clone(),
+            accounts.token_owner.clone(),
+            accounts.destination_token_account.clone(),
+            accounts.destination.clone(),
+            accounts.mint.clone(),
+            accounts.metadata.clone(),
+            accounts.edition.clone(),
+            accounts.token_record.clone(),
+            accounts.destination_token_record.clone(),
+            accounts.authority.clone(),
+            accounts.payer.clone(),
+            accounts.system_program.clone(),
+            accounts.sysvar_instructions.clone(),
+            accounts.token_program.clone(),
+            accounts.associated_token_program.clone(),
+            accounts.authorization_rules_program.clone(),
+            accounts.authorization_rules.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Moves one token out of `accounts.token_account`, going through Token Metadata
+/// for programmable NFTs and a plain SPL transfer for everything else.
+pub fn invoke_transfer_asset(
+    accounts: &PNFTTransferAccounts,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let metadata = assert_metadata_for_mint(&accounts.metadata, &accounts.mint.key())?;
+    if metadata.token_standard == Some(TokenStandard::ProgrammableNonFungible) {
+        return invoke_transfer_pnft(accounts, signer_seeds);
+    }
+
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            &accounts.token_program.key(),
+            &accounts.token_account.key(),
+            &accounts.destination_token_account.key(),
+            &accounts.authority.key(),
+            &[],
+            1,
+        )?,
+        &[
+            accounts.token_program.clone(),
+            accounts.token_account.clone(),
+            accounts.destination_token_account.clone(),
+            accounts.authority.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+pub fn transfer_pnft_dreamer<'info>(
+    ctx: Context<'_, '_, '_, 'info, RewardPNFTDreamer<'info>>,
+    royalty: Option<RoyaltyArgs>,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    assert_token_unlocked(&ctx.accounts.token_record)?;
+    consume_rate_limit(&ctx.accounts.state_config)?;
+    assert_withdraw_destination(
+        &ctx.accounts.state_config,
+        &ctx.accounts.deposit_record,
+        &ctx.accounts.destination.key(),
+    )?;
+    assert_allowlisted_destination(
+        &ctx.accounts.owner.key(),
+        &ctx.accounts.state_config,
+        &ctx.accounts.destination.key(),
+        &proof,
+        &ctx.accounts.claim_marker,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    if let Some(royalty) = royalty {
+        let metadata = assert_metadata_for_mint(
+            &ctx.accounts.metadata.to_account_info(),
+            &ctx.accounts.mint.key(),
+        )?;
+        pay_royalties(
+            &royalty,
+            &metadata,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+    }
+
     invoke_signed(
         &Instruction {
             program_id: mpl_token_metadata::id(),
@@ -205,10 +394,32 @@ pub fn transfer_pnft_dreamer(ctx: Context<RewardPNFTDreamer>) -> Result<()> {
             &[*&ctx.accounts.owner.bump],
         ]],
     )?;
+    record_withdrawal(&ctx.accounts.deposit_record, &ctx.accounts.state_config)?;
     Ok(())
 }
 
-pub fn transfer_pnft_swap(ctx: Context<RewardPNFTSwap>) -> Result<()> {
+pub fn transfer_pnft_swap<'info>(
+    ctx: Context<'_, '_, '_, 'info, RewardPNFTSwap<'info>>,
+    royalty: Option<RoyaltyArgs>,
+) -> Result<()> {
+    assert_token_unlocked(&ctx.accounts.token_record)?;
+    consume_rate_limit(&ctx.accounts.state_config)?;
+
+    if let Some(royalty) = royalty {
+        let metadata = assert_metadata_for_mint(
+            &ctx.accounts.metadata.to_account_info(),
+            &ctx.accounts.mint.key(),
+        )?;
+        pay_royalties(
+            &royalty,
+            &metadata,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+    }
+
     invoke_signed(
         &Instruction {
             program_id: mpl_token_metadata::id(),
@@ -279,10 +490,13 @@ pub fn transfer_pnft_swap(ctx: Context<RewardPNFTSwap>) -> Result<()> {
             &[*&ctx.accounts.owner.bump],
         ]],
     )?;
+    record_withdrawal(&ctx.accounts.deposit_record, &ctx.accounts.state_config)?;
     Ok(())
 }
 
 pub fn transfer_pnft(ctx: Context<RewardPNFTWallet>) -> Result<()> {
+    assert_token_unlocked(&ctx.accounts.token_record)?;
+
     invoke(
         &Instruction {
             program_id: mpl_token_metadata::id(),
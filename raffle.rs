@@ -0,0 +1,655 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::solana_program::sysvar::slot_hashes;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::deposit::record_withdrawal;
+use crate::errors::DreamersError;
+use crate::errors::ErrorCode;
+use crate::pnft::invoke_transfer_asset;
+use crate::pnft::PNFTTransferAccounts;
+use crate::randomness::randomness_address;
+use crate::randomness::read_randomness;
+use crate::state::DreamerState;
+use crate::state_config::consume_rate_limit;
+use crate::state_config::record_closed_position;
+use crate::state_config::record_open_position;
+use crate::state_config::StateConfig;
+use crate::state_config::STATE_CONFIG_SIZE;
+use crate::utils::close_escrow_token_account;
+use crate::utils::transfer_lamports_from_pda;
+
+pub const RAFFLE_SIZE: usize =
+    8 + 1 + 32 + 32 + 32 + 8 + 4 + 4 + 8 + (1 + 32) + (1 + 4) + 1 + 1 + 4;
+pub const RAFFLE_TICKET_SIZE: usize = 8 + 1 + 32 + 4 + 32;
+/// Raffles not drawn this long after their end date can be cancelled by anyone
+pub const RAFFLE_DRAW_GRACE_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// Raffle for a prize escrowed from a dreamer PDA. Ticket payments are held by
+/// the raffle until it is settled, or refunded if it is cancelled.
+#[account]
+pub struct Raffle {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    pub prize_mint: Pubkey,
+    /// Lamports per ticket, paid to `authority` on settlement
+    pub ticket_price: u64,
+    pub max_tickets: u32,
+    pub tickets_sold: u32,
+    pub end_date: i64,
+    /// Seed of the oracle request the winner is drawn from, set by `lock_raffle`
+    pub randomness_seed: Option<[u8; 32]>,
+    pub winning_ticket: Option<u32>,
+    pub settled: bool,
+    pub cancelled: bool,
+    pub tickets_refunded: u32,
+}
+
+impl Raffle {
+    /// Signer seeds of the raffle PDA, which owns the prize escrow
+    pub fn seeds(&self) -> [&[u8]; 4] {
+        [
+            b"raffle",
+            self.state.as_ref(),
+            self.prize_mint.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
+
+#[account]
+pub struct RaffleTicket {
+    pub bump: u8,
+    pub raffle: Pubkey,
+    pub index: u32,
+    pub holder: Pubkey,
+}
+
+#[event]
+pub struct RaffleLocked {
+    pub raffle: Pubkey,
+    pub randomness_seed: [u8; 32],
+    pub randomness: Pubkey,
+}
+
+#[event]
+pub struct RaffleDrawn {
+    pub raffle: Pubkey,
+    pub winning_ticket: u32,
+}
+
+#[event]
+pub struct RaffleCancelled {
+    pub raffle: Pubkey,
+    pub tickets_sold: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreateRaffleArgs {
+    pub ticket_price: u64,
+    pub max_tickets: u32,
+    pub end_date: i64,
+}
+
+#[derive(Accounts)]
+pub struct CreateRaffle<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_dreamer: Box<Account<'info, DreamerState>>,
+    #[account(
+        init,
+        payer = authority,
+        space = RAFFLE_SIZE,
+        seeds = [b"raffle", pda_dreamer.key().as_ref(), prize_mint.key().as_ref()],
+        bump,
+    )]
+    pub raffle: Box<Account<'info, Raffle>>,
+    pub prize_mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        prize_token_account.owner == pda_dreamer.key()
+            && prize_token_account.mint == prize_mint.key()
+            && prize_token_account.amount == 1
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub prize_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_dreamer.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Box<Account<'info, StateConfig>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = prize_mint,
+        associated_token::authority = raffle,
+    )]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyRaffleTicket<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(mut)]
+    pub raffle: Box<Account<'info, Raffle>>,
+    #[account(
+        init,
+        payer = buyer,
+        space = RAFFLE_TICKET_SIZE,
+        seeds = [b"raffle_ticket", raffle.key().as_ref(), &raffle.tickets_sold.to_le_bytes()],
+        bump,
+    )]
+    pub raffle_ticket: Box<Account<'info, RaffleTicket>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LockRaffle<'info> {
+    #[account(mut)]
+    pub raffle: Box<Account<'info, Raffle>>,
+    /// CHECK: This is not dangerous because its address is the slot hashes sysvar
+    #[account(address = slot_hashes::id())]
+    pub recent_slothashes: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DrawRaffle<'info> {
+    #[account(mut)]
+    pub raffle: Box<Account<'info, Raffle>>,
+    /// CHECK: This is not dangerous because we check its address and read it in read_randomness
+    pub randomness: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleRaffle<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        has_one = prize_mint @ ErrorCode::InvalidRaffle,
+        has_one = authority @ ErrorCode::InvalidRaffle,
+    )]
+    pub raffle: Box<Account<'info, Raffle>>,
+    /// CHECK: This is not dangerous because it must be the raffle authority
+    #[account(mut)]
+    pub authority: UncheckedAccount<'info>,
+    #[account(constraint =
+        raffle_ticket.raffle == raffle.key()
+            && Some(raffle_ticket.index) == raffle.winning_ticket
+        @ ErrorCode::InvalidRaffleTicket)]
+    pub raffle_ticket: Box<Account<'info, RaffleTicket>>,
+    /// CHECK: This is not dangerous because it must be the winning ticket holder
+    #[account(address = raffle_ticket.holder @ ErrorCode::InvalidRaffleTicket)]
+    pub winner: UncheckedAccount<'info>,
+    pub prize_mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        escrow_token_account.owner == raffle.key() && escrow_token_account.mint == prize_mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we only update it in consume_rate_limit and record_withdrawal
+    #[account(mut, seeds = [b"state_config", raffle.state.as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we only update it in record_withdrawal
+    #[account(mut, seeds = [b"deposit", raffle.state.as_ref(), prize_mint.key().as_ref()], bump)]
+    pub deposit_record: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = prize_mint,
+        associated_token::authority = winner,
+    )]
+    pub winner_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub winner_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The raffle authority can cancel until the raffle is locked for the draw.
+/// Anyone can cancel a raffle left undrawn for `RAFFLE_DRAW_GRACE_SECONDS`
+/// after it ended.
+#[derive(Accounts)]
+pub struct CancelRaffle<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        constraint = pda_dreamer.key() == raffle.state @ ErrorCode::InvalidRaffle,
+        constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_dreamer: Box<Account<'info, DreamerState>>,
+    #[account(
+        mut,
+        has_one = prize_mint @ ErrorCode::InvalidRaffle,
+        has_one = authority @ ErrorCode::InvalidRaffle,
+    )]
+    pub raffle: Box<Account<'info, Raffle>>,
+    /// CHECK: This is not dangerous because it must be the raffle authority
+    #[account(mut)]
+    pub authority: UncheckedAccount<'info>,
+    pub prize_mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        escrow_token_account.owner == raffle.key() && escrow_token_account.mint == prize_mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = prize_mint,
+        associated_token::authority = pda_dreamer,
+    )]
+    pub prize_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub escrow_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundRaffleTicket<'info> {
+    #[account(mut, constraint = raffle.cancelled @ ErrorCode::InvalidRaffle)]
+    pub raffle: Box<Account<'info, Raffle>>,
+    #[account(
+        mut,
+        close = holder,
+        has_one = raffle @ ErrorCode::InvalidRaffleTicket,
+        has_one = holder @ ErrorCode::InvalidRaffleTicket,
+    )]
+    pub raffle_ticket: Box<Account<'info, RaffleTicket>>,
+    /// CHECK: This is not dangerous because it must be the ticket holder
+    #[account(mut)]
+    pub holder: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRaffle<'info> {
+    #[account(
+        mut,
+        close = authority,
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = raffle.settled
+            || (raffle.cancelled && raffle.tickets_refunded == raffle.tickets_sold)
+            @ ErrorCode::CannotCloseRaffleWithTickets,
+    )]
+    pub raffle: Box<Account<'info, Raffle>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// CHECK: This is not dangerous because we only update it in record_closed_position
+    #[account(mut, seeds = [b"state_config", raffle.state.as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+}
+
+/// Escrows the prize from the dreamer PDA in an account owned by the raffle.
+pub fn create_raffle(ctx: Context<CreateRaffle>, args: CreateRaffleArgs) -> Result<()> {
+    msg!(format!("Creating Raffle From Dreamer Account").as_str());
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        args.max_tickets > 0 && args.end_date > now,
+        ErrorCode::InvalidRaffle
+    );
+
+    let pda_dreamer = &ctx.accounts.pda_dreamer;
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.prize_token_account.to_account_info(),
+            token_owner: pda_dreamer.to_account_info(),
+            destination_token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: ctx.accounts.raffle.to_account_info(),
+            mint: ctx.accounts.prize_mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.token_record.to_account_info(),
+            destination_token_record: ctx.accounts.escrow_token_record.to_account_info(),
+            authority: pda_dreamer.to_account_info(),
+            payer: ctx.accounts.authority.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[&[
+            b"dreamer",
+            &[pda_dreamer.id],
+            pda_dreamer.authority.key().as_ref(),
+            &[pda_dreamer.bump],
+        ]],
+    )?;
+
+    let raffle = &mut ctx.accounts.raffle;
+    raffle.bump = *ctx.bumps.get("raffle").unwrap();
+    raffle.state = ctx.accounts.pda_dreamer.key();
+    raffle.authority = ctx.accounts.authority.key();
+    raffle.prize_mint = ctx.accounts.prize_mint.key();
+    raffle.ticket_price = args.ticket_price;
+    raffle.max_tickets = args.max_tickets;
+    raffle.tickets_sold = 0;
+    raffle.end_date = args.end_date;
+    raffle.randomness_seed = None;
+    raffle.winning_ticket = None;
+    raffle.settled = false;
+    raffle.cancelled = false;
+    raffle.tickets_refunded = 0;
+
+    record_open_position(
+        &mut ctx.accounts.state_config,
+        *ctx.bumps.get("state_config").unwrap(),
+        ctx.accounts.pda_dreamer.key(),
+    );
+    Ok(())
+}
+
+pub fn buy_raffle_ticket(ctx: Context<BuyRaffleTicket>) -> Result<()> {
+    let raffle = &mut ctx.accounts.raffle;
+    require!(!raffle.cancelled, ErrorCode::InvalidRaffle);
+    require!(
+        Clock::get()?.unix_timestamp < raffle.end_date,
+        ErrorCode::RaffleEnded
+    );
+    require!(
+        raffle.tickets_sold < raffle.max_tickets,
+        ErrorCode::RaffleSoldOut
+    );
+
+    if raffle.ticket_price > 0 {
+        invoke(
+            &system_instruction::transfer(
+                &ctx.accounts.buyer.key(),
+                &raffle.key(),
+                raffle.ticket_price,
+            ),
+            &[
+                ctx.accounts.buyer.to_account_info(),
+                raffle.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    let raffle_ticket = &mut ctx.accounts.raffle_ticket;
+    raffle_ticket.bump = *ctx.bumps.get("raffle_ticket").unwrap();
+    raffle_ticket.raffle = raffle.key();
+    raffle_ticket.index = raffle.tickets_sold;
+    raffle_ticket.holder = ctx.accounts.buyer.key();
+    raffle.tickets_sold = raffle
+        .tickets_sold
+        .checked_add(1)
+        .ok_or(error!(ErrorCode::RaffleSoldOut))?;
+    Ok(())
+}
+
+/// Fixes the oracle request seed once ticket sales are over. The seed mixes in
+/// the latest slot hash, so nobody can request the randomness before the raffle
+/// ends. Anyone may call this and then request randomness with the seed.
+pub fn lock_raffle(ctx: Context<LockRaffle>) -> Result<()> {
+    let raffle = &mut ctx.accounts.raffle;
+    require!(!raffle.cancelled, ErrorCode::InvalidRaffle);
+    require!(
+        raffle.randomness_seed.is_none(),
+        ErrorCode::RaffleAlreadyDrawn
+    );
+    require!(
+        Clock::get()?.unix_timestamp >= raffle.end_date
+            || raffle.tickets_sold == raffle.max_tickets,
+        ErrorCode::RaffleNotEnded
+    );
+    require!(raffle.tickets_sold > 0, ErrorCode::InvalidRaffleTicket);
+
+    // slot hashes are a length prefix followed by (slot, hash) pairs, newest first
+    let slot_hashes = ctx.accounts.recent_slothashes.try_borrow_data()?;
+    let recent_hash = &slot_hashes[16..48];
+    let randomness_seed = keccak::hashv(&[
+        raffle.key().as_ref(),
+        &raffle.tickets_sold.to_le_bytes(),
+        recent_hash,
+    ])
+    .0;
+    raffle.randomness_seed = Some(randomness_seed);
+
+    emit!(RaffleLocked {
+        raffle: raffle.key(),
+        randomness_seed,
+        randomness: randomness_address(&randomness_seed),
+    });
+    Ok(())
+}
+
+/// Picks the winning ticket from the fulfilled oracle request. Anyone may call
+/// this, the result only depends on the randomness account.
+pub fn draw_raffle(ctx: Context<DrawRaffle>) -> Result<()> {
+    let raffle = &mut ctx.accounts.raffle;
+    require!(!raffle.cancelled, ErrorCode::InvalidRaffle);
+    require!(
+        raffle.winning_ticket.is_none(),
+        ErrorCode::RaffleAlreadyDrawn
+    );
+    let randomness_seed = raffle.randomness_seed.ok_or(ErrorCode::RaffleNotEnded)?;
+    require_keys_eq!(
+        ctx.accounts.randomness.key(),
+        randomness_address(&randomness_seed),
+        ErrorCode::InvalidRandomnessAccount
+    );
+
+    let value = read_randomness(&ctx.accounts.randomness)?;
+    let mut seed = [0u8; 8];
+    seed.copy_from_slice(&value[..8]);
+    let winning_ticket = (u64::from_le_bytes(seed) % raffle.tickets_sold as u64) as u32;
+    raffle.winning_ticket = Some(winning_ticket);
+
+    emit!(RaffleDrawn {
+        raffle: raffle.key(),
+        winning_ticket,
+    });
+    Ok(())
+}
+
+/// Sends the escrowed prize to the winner and the ticket proceeds to the
+/// raffle authority.
+pub fn settle_raffle(ctx: Context<SettleRaffle>) -> Result<()> {
+    msg!(format!("Settling Raffle").as_str());
+
+    let raffle = &ctx.accounts.raffle;
+    require!(!raffle.settled, ErrorCode::RaffleAlreadySettled);
+    require!(raffle.winning_ticket.is_some(), ErrorCode::RaffleNotDrawn);
+
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            token_owner: raffle.to_account_info(),
+            destination_token_account: ctx.accounts.winner_token_account.to_account_info(),
+            destination: ctx.accounts.winner.to_account_info(),
+            mint: ctx.accounts.prize_mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.escrow_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.winner_token_record.to_account_info(),
+            authority: raffle.to_account_info(),
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[&raffle.seeds()],
+    )?;
+    close_escrow_token_account(
+        &ctx.accounts.escrow_token_account.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        &raffle.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &[&raffle.seeds()],
+    )?;
+
+    consume_rate_limit(&ctx.accounts.state_config)?;
+    record_withdrawal(&ctx.accounts.deposit_record, &ctx.accounts.state_config)?;
+
+    let proceeds = raffle
+        .ticket_price
+        .checked_mul(raffle.tickets_sold as u64)
+        .ok_or(error!(ErrorCode::InvalidRaffle))?;
+    transfer_lamports_from_pda(
+        &raffle.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        proceeds,
+    )?;
+
+    ctx.accounts.raffle.settled = true;
+    Ok(())
+}
+
+/// Returns the escrowed prize to the dreamer PDA. Ticket holders then get their
+/// payments back with `refund_raffle_ticket`.
+pub fn cancel_raffle(ctx: Context<CancelRaffle>) -> Result<()> {
+    msg!(format!("Cancelling Raffle").as_str());
+
+    let raffle = &ctx.accounts.raffle;
+    require!(!raffle.cancelled, ErrorCode::InvalidRaffle);
+    require!(
+        raffle.winning_ticket.is_none(),
+        ErrorCode::RaffleAlreadyDrawn
+    );
+    let undrawn_after_grace =
+        Clock::get()?.unix_timestamp >= raffle.end_date + RAFFLE_DRAW_GRACE_SECONDS;
+    if ctx.accounts.payer.key() == raffle.authority {
+        // Once the draw is requested the authority can no longer veto its result
+        require!(
+            raffle.randomness_seed.is_none() || undrawn_after_grace,
+            ErrorCode::RaffleDrawRequested
+        );
+    } else {
+        require!(undrawn_after_grace, DreamersError::Unauthorized);
+    }
+
+    invoke_transfer_asset(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            token_owner: raffle.to_account_info(),
+            destination_token_account: ctx.accounts.prize_token_account.to_account_info(),
+            destination: ctx.accounts.pda_dreamer.to_account_info(),
+            mint: ctx.accounts.prize_mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.escrow_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.token_record.to_account_info(),
+            authority: raffle.to_account_info(),
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[&raffle.seeds()],
+    )?;
+    close_escrow_token_account(
+        &ctx.accounts.escrow_token_account.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        &raffle.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &[&raffle.seeds()],
+    )?;
+
+    let raffle = &mut ctx.accounts.raffle;
+    raffle.cancelled = true;
+    emit!(RaffleCancelled {
+        raffle: raffle.key(),
+        tickets_sold: raffle.tickets_sold,
+    });
+    Ok(())
+}
+
+/// Pays a ticket holder of a cancelled raffle back and closes their ticket.
+pub fn refund_raffle_ticket(ctx: Context<RefundRaffleTicket>) -> Result<()> {
+    let raffle = &mut ctx.accounts.raffle;
+    transfer_lamports_from_pda(
+        &raffle.to_account_info(),
+        &ctx.accounts.holder.to_account_info(),
+        raffle.ticket_price,
+    )?;
+    raffle.tickets_refunded = raffle
+        .tickets_refunded
+        .checked_add(1)
+        .ok_or(error!(ErrorCode::InvalidRaffleTicket))?;
+    Ok(())
+}
+
+pub fn close_raffle(ctx: Context<CloseRaffle>) -> Result<()> {
+    record_closed_position(&ctx.accounts.state_config)
+}
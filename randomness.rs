@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey;
+
+use crate::errors::ErrorCode;
+
+/// ORAO VRF, the oracle raffles draw their winners from.
+pub const ORAO_VRF_PROGRAM_ID: Pubkey = pubkey!("VRFzZoJdhFWL8rkvu87LpKM3RbcVezpMEc6X5GVDr7y");
+pub const ORAO_RANDOMNESS_SEED: &[u8] = b"orao-vrf-randomness-request";
+/// ORAO randomness accounts hold the discriminator and request seed, then 64
+/// bytes of randomness that stay zeroed until the request is fulfilled.
+pub const ORAO_RANDOMNESS_OFFSET: usize = 8 + 32;
+
+/// Address of the randomness account for a request made with `seed`.
+#[cfg(not(feature = "mock-randomness"))]
+pub fn randomness_address(seed: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[ORAO_RANDOMNESS_SEED, seed], &ORAO_VRF_PROGRAM_ID).0
+}
+
+/// Reads the fulfilled randomness from `randomness`, failing until the oracle
+/// has answered the request.
+#[cfg(not(feature = "mock-randomness"))]
+pub fn read_randomness(randomness: &AccountInfo) -> Result<[u8; 32]> {
+    require_keys_eq!(
+        *randomness.owner,
+        ORAO_VRF_PROGRAM_ID,
+        ErrorCode::InvalidRandomnessAccount
+    );
+    let data = randomness.try_borrow_data()?;
+    require!(
+        data.len() >= ORAO_RANDOMNESS_OFFSET + 64,
+        ErrorCode::InvalidRandomnessAccount
+    );
+    let fulfilled = &data[ORAO_RANDOMNESS_OFFSET..ORAO_RANDOMNESS_OFFSET + 64];
+    require!(
+        fulfilled.iter().any(|byte| *byte != 0),
+        ErrorCode::RandomnessNotRevealed
+    );
+    let mut value = [0u8; 32];
+    value.copy_from_slice(&fulfilled[..32]);
+    Ok(value)
+}
+
+#[cfg(feature = "mock-randomness")]
+pub use mock::*;
+
+/// Randomness source for local testing only, compiled in with the
+/// `mock-randomness` feature in place of the oracle. Anyone can reveal it.
+#[cfg(feature = "mock-randomness")]
+mod mock {
+    use super::*;
+
+    pub const MOCK_RANDOMNESS_SIZE: usize = 8 + 1 + 32 + (1 + 32);
+
+    #[account]
+    pub struct MockRandomness {
+        pub bump: u8,
+        pub seed: [u8; 32],
+        pub value: Option<[u8; 32]>,
+    }
+
+    #[derive(Accounts)]
+    #[instruction(seed: [u8; 32])]
+    pub struct InitMockRandomness<'info> {
+        #[account(
+            init,
+            payer = payer,
+            space = MOCK_RANDOMNESS_SIZE,
+            seeds = [b"mock_randomness", seed.as_ref()],
+            bump,
+        )]
+        pub mock_randomness: Account<'info, MockRandomness>,
+        #[account(mut)]
+        pub payer: Signer<'info>,
+        pub system_program: Program<'info, System>,
+    }
+
+    #[derive(Accounts)]
+    pub struct RevealMockRandomness<'info> {
+        #[account(mut)]
+        pub mock_randomness: Account<'info, MockRandomness>,
+    }
+
+    pub fn randomness_address(seed: &[u8; 32]) -> Pubkey {
+        Pubkey::find_program_address(&[b"mock_randomness", seed], &crate::id()).0
+    }
+
+    pub fn read_randomness(randomness: &AccountInfo) -> Result<[u8; 32]> {
+        require_keys_eq!(
+            *randomness.owner,
+            crate::id(),
+            ErrorCode::InvalidRandomnessAccount
+        );
+        let mock = Account::<MockRandomness>::try_from(randomness)?;
+        mock.value.ok_or(error!(ErrorCode::RandomnessNotRevealed))
+    }
+
+    pub fn init_mock_randomness(ctx: Context<InitMockRandomness>, seed: [u8; 32]) -> Result<()> {
+        let mock_randomness = &mut ctx.accounts.mock_randomness;
+        mock_randomness.bump = *ctx.bumps.get("mock_randomness").unwrap();
+        mock_randomness.seed = seed;
+        mock_randomness.value = None;
+        Ok(())
+    }
+
+    pub fn reveal_mock_randomness(ctx: Context<RevealMockRandomness>, value: [u8; 32]) -> Result<()> {
+        let mock_randomness = &mut ctx.accounts.mock_randomness;
+        require!(
+            mock_randomness.value.is_none(),
+            ErrorCode::InvalidRandomnessAccount
+        );
+        mock_randomness.value = Some(value);
+        Ok(())
+    }
+}
@@ -0,0 +1,504 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+use mpl_token_metadata::instruction::DelegateArgs;
+use mpl_token_metadata::instruction::RevokeArgs;
+use mpl_token_metadata::state::TokenStandard;
+
+use crate::delegate::delegate_pnft;
+use crate::delegate::lock_pnft;
+use crate::delegate::revoke_pnft;
+use crate::delegate::unlock_pnft;
+use crate::delegate::PNFTAccounts;
+use crate::errors::ErrorCode;
+use crate::pnft::invoke_transfer_pnft;
+use crate::pnft::PNFTTransferAccounts;
+use crate::utils::assert_metadata_for_mint;
+
+pub const RENTAL_SIZE: usize = 8 + 1 + 32 + 32 + 8 + 4 + (1 + 32) + 8;
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// A pNFT offered for rent. While listed the rental PDA is the owner's transfer
+/// delegate, while rented it is the renter's locked transfer delegate and holds
+/// the lock, so only this program can move the asset back to `owner`.
+#[account]
+pub struct Rental {
+    pub bump: u8,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    /// Lamports per day, paid to `owner` up front
+    pub price_per_day: u64,
+    pub max_days: u32,
+    pub renter: Option<Pubkey>,
+    pub rented_until: i64,
+}
+
+#[event]
+pub struct AssetRented {
+    pub rental: Pubkey,
+    pub renter: Pubkey,
+    pub mint: Pubkey,
+    pub rented_until: i64,
+}
+
+#[event]
+pub struct RentalReclaimed {
+    pub rental: Pubkey,
+    pub renter: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct ListRental<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        init,
+        payer = owner,
+        space = RENTAL_SIZE,
+        seeds = [b"rental", owner.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub rental: Box<Account<'info, Rental>>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        owner_token_account.owner == owner.key() && owner_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub owner_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub owner_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in assert_metadata_for_mint
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DelistRental<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::InvalidRental,
+        has_one = mint @ ErrorCode::InvalidRental,
+        constraint = rental.renter.is_none() @ ErrorCode::RentalUnavailable,
+    )]
+    pub rental: Box<Account<'info, Rental>>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        owner_token_account.owner == owner.key() && owner_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub owner_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub owner_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RentAsset<'info> {
+    #[account(mut)]
+    pub renter: Signer<'info>,
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::InvalidRental,
+        has_one = mint @ ErrorCode::InvalidRental,
+        constraint = rental.renter.is_none() @ ErrorCode::RentalUnavailable,
+    )]
+    pub rental: Box<Account<'info, Rental>>,
+    /// CHECK: This is not dangerous because it must match the rental owner
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        owner_token_account.owner == owner.key() && owner_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub owner_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = renter,
+        associated_token::mint = mint,
+        associated_token::authority = renter,
+    )]
+    pub renter_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub owner_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub renter_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimExpiredRental<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::InvalidRental,
+        has_one = mint @ ErrorCode::InvalidRental,
+        constraint = rental.renter == Some(renter.key()) @ ErrorCode::InvalidRental,
+    )]
+    pub rental: Box<Account<'info, Rental>>,
+    /// CHECK: This is not dangerous because it must match the rental owner
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because it must match the rental renter
+    pub renter: UncheckedAccount<'info>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        renter_token_account.owner == renter.key() && renter_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub renter_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+    )]
+    pub owner_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub renter_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub owner_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ListRental<'info> {
+    fn pnft_accounts(&self) -> PNFTAccounts<'info> {
+        PNFTAccounts {
+            token_owner: self.owner.to_account_info(),
+            token_account: self.owner_token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            metadata: self.metadata.to_account_info(),
+            edition: self.edition.to_account_info(),
+            token_record: self.owner_token_record.to_account_info(),
+            payer: self.owner.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            sysvar_instructions: self.sysvar_instructions.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            authorization_rules_program: self.authorization_rules_program.to_account_info(),
+            authorization_rules: self.authorization_rules.to_account_info(),
+            token_metadata_program: self.token_metadata_program.to_account_info(),
+        }
+    }
+}
+
+impl<'info> DelistRental<'info> {
+    fn pnft_accounts(&self) -> PNFTAccounts<'info> {
+        PNFTAccounts {
+            token_owner: self.owner.to_account_info(),
+            token_account: self.owner_token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            metadata: self.metadata.to_account_info(),
+            edition: self.edition.to_account_info(),
+            token_record: self.owner_token_record.to_account_info(),
+            payer: self.owner.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            sysvar_instructions: self.sysvar_instructions.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            authorization_rules_program: self.authorization_rules_program.to_account_info(),
+            authorization_rules: self.authorization_rules.to_account_info(),
+            token_metadata_program: self.token_metadata_program.to_account_info(),
+        }
+    }
+}
+
+impl<'info> RentAsset<'info> {
+    fn renter_pnft_accounts(&self) -> PNFTAccounts<'info> {
+        PNFTAccounts {
+            token_owner: self.renter.to_account_info(),
+            token_account: self.renter_token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            metadata: self.metadata.to_account_info(),
+            edition: self.edition.to_account_info(),
+            token_record: self.renter_token_record.to_account_info(),
+            payer: self.renter.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            sysvar_instructions: self.sysvar_instructions.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            authorization_rules_program: self.authorization_rules_program.to_account_info(),
+            authorization_rules: self.authorization_rules.to_account_info(),
+            token_metadata_program: self.token_metadata_program.to_account_info(),
+        }
+    }
+}
+
+impl<'info> ReclaimExpiredRental<'info> {
+    fn renter_pnft_accounts(&self) -> PNFTAccounts<'info> {
+        PNFTAccounts {
+            token_owner: self.renter.to_account_info(),
+            token_account: self.renter_token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            metadata: self.metadata.to_account_info(),
+            edition: self.edition.to_account_info(),
+            token_record: self.renter_token_record.to_account_info(),
+            payer: self.payer.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            sysvar_instructions: self.sysvar_instructions.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            authorization_rules_program: self.authorization_rules_program.to_account_info(),
+            authorization_rules: self.authorization_rules.to_account_info(),
+            token_metadata_program: self.token_metadata_program.to_account_info(),
+        }
+    }
+}
+
+pub fn list_rental(ctx: Context<ListRental>, price_per_day: u64, max_days: u32) -> Result<()> {
+    msg!(format!("Listing pNFT {} For Rent", ctx.accounts.mint.key()).as_str());
+
+    require!(max_days > 0, ErrorCode::InvalidRentalDuration);
+    let metadata = assert_metadata_for_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.key(),
+    )?;
+    require!(
+        metadata.token_standard == Some(TokenStandard::ProgrammableNonFungible),
+        ErrorCode::InvalidRental
+    );
+
+    delegate_pnft(
+        &ctx.accounts.pnft_accounts(),
+        &ctx.accounts.rental.to_account_info(),
+        &ctx.accounts.owner.to_account_info(),
+        DelegateArgs::TransferV1 {
+            amount: 1,
+            authorization_data: None,
+        },
+        &[],
+    )?;
+
+    let rental = &mut ctx.accounts.rental;
+    rental.bump = *ctx.bumps.get("rental").unwrap();
+    rental.owner = ctx.accounts.owner.key();
+    rental.mint = ctx.accounts.mint.key();
+    rental.price_per_day = price_per_day;
+    rental.max_days = max_days;
+    rental.renter = None;
+    rental.rented_until = 0;
+    Ok(())
+}
+
+pub fn delist_rental(ctx: Context<DelistRental>) -> Result<()> {
+    msg!(format!("Delisting pNFT {} From Rent", ctx.accounts.mint.key()).as_str());
+
+    revoke_pnft(
+        &ctx.accounts.pnft_accounts(),
+        &ctx.accounts.rental.to_account_info(),
+        &ctx.accounts.owner.to_account_info(),
+        RevokeArgs::TransferV1,
+        &[],
+    )?;
+
+    Ok(())
+}
+
+/// Pays for `days` of rent and moves the asset to the renter, where it is locked
+/// with the rental PDA as a locked transfer delegate back to the owner.
+pub fn rent_asset(ctx: Context<RentAsset>, days: u32) -> Result<()> {
+    msg!(format!("Renting pNFT {}", ctx.accounts.mint.key()).as_str());
+
+    let rental = &ctx.accounts.rental;
+    require!(
+        days > 0 && days <= rental.max_days,
+        ErrorCode::InvalidRentalDuration
+    );
+
+    let price = rental
+        .price_per_day
+        .checked_mul(days as u64)
+        .ok_or(ErrorCode::InvalidRentalDuration)?;
+    if price > 0 {
+        invoke(
+            &system_instruction::transfer(&ctx.accounts.renter.key(), &rental.owner, price),
+            &[
+                ctx.accounts.renter.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    let rental_info = rental.to_account_info();
+    let rental_seeds: &[&[u8]] = &[
+        b"rental",
+        rental.owner.as_ref(),
+        rental.mint.as_ref(),
+        &[rental.bump],
+    ];
+    invoke_transfer_pnft(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.owner_token_account.to_account_info(),
+            token_owner: ctx.accounts.owner.to_account_info(),
+            destination_token_account: ctx.accounts.renter_token_account.to_account_info(),
+            destination: ctx.accounts.renter.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.owner_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.renter_token_record.to_account_info(),
+            authority: rental_info.clone(),
+            payer: ctx.accounts.renter.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[rental_seeds],
+    )?;
+
+    let accounts = ctx.accounts.renter_pnft_accounts();
+    delegate_pnft(
+        &accounts,
+        &rental_info,
+        &ctx.accounts.renter.to_account_info(),
+        DelegateArgs::LockedTransferV1 {
+            amount: 1,
+            locked_address: rental.owner,
+            authorization_data: None,
+        },
+        &[],
+    )?;
+    lock_pnft(&accounts, &rental_info, &[rental_seeds])?;
+
+    let rented_until = Clock::get()?
+        .unix_timestamp
+        .checked_add(days as i64 * SECONDS_PER_DAY)
+        .ok_or(ErrorCode::InvalidRentalDuration)?;
+    let rental = &mut ctx.accounts.rental;
+    rental.renter = Some(ctx.accounts.renter.key());
+    rental.rented_until = rented_until;
+
+    emit!(AssetRented {
+        rental: rental.key(),
+        renter: ctx.accounts.renter.key(),
+        mint: rental.mint,
+        rented_until,
+    });
+    Ok(())
+}
+
+/// Returns a rented asset to its owner once the rental has expired. Anyone may
+/// call this, the rental account is closed into the owner.
+pub fn reclaim_expired_rental(ctx: Context<ReclaimExpiredRental>) -> Result<()> {
+    msg!(format!("Reclaiming Rented pNFT {}", ctx.accounts.mint.key()).as_str());
+
+    let rental = &ctx.accounts.rental;
+    require!(
+        Clock::get()?.unix_timestamp >= rental.rented_until,
+        ErrorCode::RentalNotExpired
+    );
+
+    let rental_info = rental.to_account_info();
+    let rental_seeds: &[&[u8]] = &[
+        b"rental",
+        rental.owner.as_ref(),
+        rental.mint.as_ref(),
+        &[rental.bump],
+    ];
+    unlock_pnft(
+        &ctx.accounts.renter_pnft_accounts(),
+        &rental_info,
+        &[rental_seeds],
+    )?;
+    invoke_transfer_pnft(
+        &PNFTTransferAccounts {
+            token_account: ctx.accounts.renter_token_account.to_account_info(),
+            token_owner: ctx.accounts.renter.to_account_info(),
+            destination_token_account: ctx.accounts.owner_token_account.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            token_record: ctx.accounts.renter_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.owner_token_record.to_account_info(),
+            authority: rental_info.clone(),
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        },
+        &[rental_seeds],
+    )?;
+
+    emit!(RentalReclaimed {
+        rental: rental.key(),
+        renter: ctx.accounts.renter.key(),
+        mint: rental.mint,
+    });
+    Ok(())
+}
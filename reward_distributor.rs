@@ -0,0 +1,321 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::errors::ErrorCode;
+use crate::group_stake_entry::GroupStakeEntry;
+use crate::group_stake_entry::GROUP_STAKE_ENTRY_MIN_ENTRIES;
+use crate::stake_entry::StakeEntry;
+use crate::stake_pool::StakePool;
+
+pub const REWARD_DISTRIBUTOR_SIZE: usize = 8 + 1 + 32 + 32 + 8 + 16 + 16 + 8 + 1 + (1 + 8);
+pub const REWARD_ENTRY_SIZE: usize = 8 + 1 + 32 + 32 + 16 + 8 + 8;
+
+#[account]
+pub struct RewardDistributor {
+    pub bump: u8,
+    pub stake_pool: Pubkey,
+    pub reward_mint: Pubkey,
+    /// Amount paid for every `reward_duration_seconds` staked
+    pub reward_amount: u64,
+    pub reward_duration_seconds: u128,
+    pub rewards_issued: u128,
+    pub default_multiplier: u64,
+    /// Multipliers are fixed point numbers with this many decimals
+    pub multiplier_decimals: u8,
+    /// Extra multiplier applied to entries that are part of a group
+    pub group_multiplier: Option<u64>,
+}
+
+#[account]
+pub struct RewardEntry {
+    pub bump: u8,
+    pub stake_entry: Pubkey,
+    pub reward_distributor: Pubkey,
+    pub reward_seconds_received: u128,
+    pub multiplier: u64,
+    /// `last_staked_at` of the stake `reward_seconds_received` belongs to, a new
+    /// stake starts counting from zero
+    pub staked_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RewardDistributorArgs {
+    pub reward_amount: u64,
+    pub reward_duration_seconds: u128,
+    pub default_multiplier: u64,
+    pub multiplier_decimals: u8,
+    pub group_multiplier: Option<u64>,
+}
+
+#[derive(Accounts)]
+pub struct InitRewardDistributor<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = REWARD_DISTRIBUTOR_SIZE,
+        seeds = [b"reward_distributor", stake_pool.key().as_ref()],
+        bump,
+    )]
+    pub reward_distributor: Box<Account<'info, RewardDistributor>>,
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = reward_mint,
+        associated_token::authority = reward_distributor,
+    )]
+    pub reward_distributor_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(has_one = authority @ ErrorCode::InvalidPoolAuthority)]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+    pub reward_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRewardDistributor<'info> {
+    #[account(mut, constraint =
+        reward_distributor.stake_pool == stake_pool.key()
+        @ ErrorCode::InvalidRewardDistributor)]
+    pub reward_distributor: Box<Account<'info, RewardDistributor>>,
+    #[account(has_one = authority @ ErrorCode::InvalidPoolAuthority)]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitRewardEntry<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = REWARD_ENTRY_SIZE,
+        seeds = [b"reward_entry", reward_distributor.key().as_ref(), stake_entry.key().as_ref()],
+        bump,
+    )]
+    pub reward_entry: Box<Account<'info, RewardEntry>>,
+    #[account(constraint =
+        reward_distributor.stake_pool == stake_entry.pool
+        @ ErrorCode::InvalidRewardDistributor)]
+    pub reward_distributor: Box<Account<'info, RewardDistributor>>,
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRewardEntry<'info> {
+    #[account(mut, constraint =
+        reward_entry.reward_distributor == reward_distributor.key()
+        @ ErrorCode::InvalidRewardEntry)]
+    pub reward_entry: Box<Account<'info, RewardEntry>>,
+    #[account(constraint =
+        reward_distributor.stake_pool == stake_pool.key()
+        @ ErrorCode::InvalidRewardDistributor)]
+    pub reward_distributor: Box<Account<'info, RewardDistributor>>,
+    #[account(has_one = authority @ ErrorCode::InvalidPoolAuthority)]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut,
+        constraint = reward_entry.reward_distributor == reward_distributor.key() @ ErrorCode::InvalidRewardEntry,
+        constraint = reward_entry.stake_entry == stake_entry.key() @ ErrorCode::InvalidRewardEntry)]
+    pub reward_entry: Box<Account<'info, RewardEntry>>,
+    #[account(mut, constraint =
+        reward_distributor.stake_pool == stake_entry.pool
+        @ ErrorCode::InvalidRewardDistributor)]
+    pub reward_distributor: Box<Account<'info, RewardDistributor>>,
+    #[account(mut, constraint =
+        reward_distributor_token_account.owner == reward_distributor.key()
+        && reward_distributor_token_account.mint == reward_distributor.reward_mint
+        @ ErrorCode::InvalidRewardDistributorTokenAccount)]
+    pub reward_distributor_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, constraint =
+        stake_entry.last_staker == user.key()
+        @ ErrorCode::InvalidLastStaker)]
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
+    /// CHECK: This is not dangerous because it is only read when it matches stake_entry.grouping_info
+    pub group_stake_entry: UncheckedAccount<'info>,
+    #[account(mut, constraint =
+        user_reward_token_account.mint == reward_distributor.reward_mint
+        @ ErrorCode::InvalidRewardMint)]
+    pub user_reward_token_account: Box<Account<'info, TokenAccount>>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn init_reward_distributor(
+    ctx: Context<InitRewardDistributor>,
+    args: RewardDistributorArgs,
+) -> Result<()> {
+    require!(
+        args.reward_duration_seconds > 0,
+        ErrorCode::InvalidRewardDistributor
+    );
+
+    let reward_distributor = &mut ctx.accounts.reward_distributor;
+    reward_distributor.bump = *ctx.bumps.get("reward_distributor").unwrap();
+    reward_distributor.stake_pool = ctx.accounts.stake_pool.key();
+    reward_distributor.reward_mint = ctx.accounts.reward_mint.key();
+    reward_distributor.reward_amount = args.reward_amount;
+    reward_distributor.reward_duration_seconds = args.reward_duration_seconds;
+    reward_distributor.rewards_issued = 0;
+    reward_distributor.default_multiplier = args.default_multiplier;
+    reward_distributor.multiplier_decimals = args.multiplier_decimals;
+    reward_distributor.group_multiplier = args.group_multiplier;
+
+    Ok(())
+}
+
+pub fn update_reward_distributor(
+    ctx: Context<UpdateRewardDistributor>,
+    args: RewardDistributorArgs,
+) -> Result<()> {
+    require!(
+        args.reward_duration_seconds > 0,
+        ErrorCode::InvalidRewardDistributor
+    );
+
+    let reward_distributor = &mut ctx.accounts.reward_distributor;
+    reward_distributor.reward_amount = args.reward_amount;
+    reward_distributor.reward_duration_seconds = args.reward_duration_seconds;
+    reward_distributor.default_multiplier = args.default_multiplier;
+    reward_distributor.multiplier_decimals = args.multiplier_decimals;
+    reward_distributor.group_multiplier = args.group_multiplier;
+
+    Ok(())
+}
+
+pub fn init_reward_entry(ctx: Context<InitRewardEntry>) -> Result<()> {
+    let reward_entry = &mut ctx.accounts.reward_entry;
+    reward_entry.bump = *ctx.bumps.get("reward_entry").unwrap();
+    reward_entry.stake_entry = ctx.accounts.stake_entry.key();
+    reward_entry.reward_distributor = ctx.accounts.reward_distributor.key();
+    reward_entry.reward_seconds_received = 0;
+    reward_entry.multiplier = ctx.accounts.reward_distributor.default_multiplier;
+    reward_entry.staked_at = ctx.accounts.stake_entry.last_staked_at;
+
+    Ok(())
+}
+
+pub fn update_reward_entry(ctx: Context<UpdateRewardEntry>, multiplier: u64) -> Result<()> {
+    ctx.accounts.reward_entry.multiplier = multiplier;
+    Ok(())
+}
+
+pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    ctx.accounts.stake_entry.update_total_stake_seconds(now);
+
+    // Seconds received by a previous staker do not count against the current one
+    let last_staked_at = ctx.accounts.stake_entry.last_staked_at;
+    if ctx.accounts.reward_entry.staked_at != last_staked_at {
+        ctx.accounts.reward_entry.reward_seconds_received = 0;
+        ctx.accounts.reward_entry.staked_at = last_staked_at;
+    }
+
+    let reward_distributor = &ctx.accounts.reward_distributor;
+    let reward_entry = &ctx.accounts.reward_entry;
+    let reward_seconds = ctx
+        .accounts
+        .stake_entry
+        .total_stake_seconds
+        .saturating_sub(reward_entry.reward_seconds_received);
+    let periods = reward_seconds / reward_distributor.reward_duration_seconds;
+    let multiplier_scale = 10u128.pow(reward_distributor.multiplier_decimals as u32);
+    let reward_for = |periods: u128| -> Result<u128> {
+        Ok(periods
+            .checked_mul(reward_distributor.reward_amount as u128)
+            .ok_or(ErrorCode::RewardAmountOverflow)?
+            .checked_mul(reward_entry.multiplier as u128)
+            .ok_or(ErrorCode::RewardAmountOverflow)?
+            / multiplier_scale)
+    };
+
+    // The group multiplier only covers seconds since the group last changed
+    let group_periods = match (
+        reward_distributor.group_multiplier,
+        ctx.accounts.stake_entry.grouping_info,
+    ) {
+        (Some(_), Some(grouping_info)) => {
+            let info = ctx.accounts.group_stake_entry.to_account_info();
+            require_keys_eq!(info.key(), grouping_info, ErrorCode::UngroupedStakeEntry);
+            let group_stake_entry = Account::<GroupStakeEntry>::try_from(&info)?;
+            if group_stake_entry.stake_entries.len() >= GROUP_STAKE_ENTRY_MIN_ENTRIES {
+                let grouped_seconds = (now - group_stake_entry.changed_at).max(0) as u128;
+                grouped_seconds.min(reward_seconds) / reward_distributor.reward_duration_seconds
+            } else {
+                0
+            }
+        }
+        _ => 0,
+    };
+    let mut reward_amount = reward_for(periods - group_periods)?;
+    if let Some(group_multiplier) = reward_distributor.group_multiplier {
+        let group_reward = reward_for(group_periods)?
+            .checked_mul(group_multiplier as u128)
+            .ok_or(ErrorCode::RewardAmountOverflow)?
+            / multiplier_scale;
+        reward_amount = reward_amount
+            .checked_add(group_reward)
+            .ok_or(ErrorCode::RewardAmountOverflow)?;
+    }
+    let reward_amount: u64 = reward_amount
+        .try_into()
+        .map_err(|_| ErrorCode::RewardAmountOverflow)?;
+    require!(
+        reward_amount <= ctx.accounts.reward_distributor_token_account.amount,
+        ErrorCode::InsufficientRewards
+    );
+
+    msg!(format!("Claiming {} reward tokens", reward_amount).as_str());
+
+    if reward_amount > 0 {
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &ctx.accounts.token_program.key(),
+                &ctx.accounts.reward_distributor_token_account.key(),
+                &ctx.accounts.user_reward_token_account.key(),
+                &reward_distributor.key(),
+                &[],
+                reward_amount,
+            )?,
+            &[
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.reward_distributor_token_account.to_account_info(),
+                ctx.accounts.user_reward_token_account.to_account_info(),
+                reward_distributor.to_account_info(),
+            ],
+            &[&[
+                b"reward_distributor",
+                reward_distributor.stake_pool.as_ref(),
+                &[reward_distributor.bump],
+            ]],
+        )?;
+    }
+
+    let reward_seconds_claimed = periods * ctx.accounts.reward_distributor.reward_duration_seconds;
+    let reward_entry = &mut ctx.accounts.reward_entry;
+    reward_entry.reward_seconds_received = reward_entry
+        .reward_seconds_received
+        .checked_add(reward_seconds_claimed)
+        .ok_or(ErrorCode::RewardAmountOverflow)?;
+
+    let reward_distributor = &mut ctx.accounts.reward_distributor;
+    reward_distributor.rewards_issued = reward_distributor
+        .rewards_issued
+        .checked_add(reward_amount as u128)
+        .ok_or(ErrorCode::RewardAmountOverflow)?;
+
+    Ok(())
+}
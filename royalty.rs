@@ -0,0 +1,119 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::TokenAccount;
+use mpl_token_metadata::state::Metadata;
+
+use crate::errors::ErrorCode;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoyaltyArgs {
+    /// Price the swap settled at, royalties are taken as a share of it. Swaps settle
+    /// off-chain, so for `transfer_pnft_*` this is whatever the caller declares and
+    /// the payment is honor-based; listings pass the on-chain listing price instead.
+    pub price: u64,
+    /// Mint of the payment, `None` when paying in SOL
+    pub payment_mint: Option<Pubkey>,
+}
+
+/// Pays `seller_fee_basis_points` of the price to the verified creators of `metadata`.
+///
+/// Unverified creators are skipped and their shares are split between the verified
+/// ones pro rata; rounding dust goes to the last verified creator so the full royalty
+/// is paid.
+///
+/// Remaining accounts are read in order: for SPL payments the payer token account
+/// first, then one account per verified creator as listed in metadata (the creator
+/// wallet for SOL, the creator token account for SPL). Returns the total paid.
+pub fn pay_royalties<'info>(
+    args: &RoyaltyArgs,
+    metadata: &Metadata,
+    payer: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<u64> {
+    let royalty = (args.price as u128)
+        .checked_mul(metadata.data.seller_fee_basis_points as u128)
+        .ok_or(ErrorCode::InvalidRoyaltyAmount)?
+        / 10_000;
+
+    let mut accounts = remaining_accounts.iter();
+    let payer_token_account = match args.payment_mint {
+        Some(payment_mint) => {
+            let info = accounts.next().ok_or(ErrorCode::MissingRoyaltyAccounts)?;
+            let token_account = Account::<TokenAccount>::try_from(info)?;
+            require!(
+                token_account.mint == payment_mint && token_account.owner == payer.key(),
+                ErrorCode::InvalidRoyaltyPaymentTokenAccount
+            );
+            Some(info)
+        }
+        None => None,
+    };
+
+    let royalty: u64 = royalty.try_into().map_err(|_| ErrorCode::InvalidRoyaltyAmount)?;
+    let creators = metadata.data.creators.iter().flatten();
+    let verified: Vec<_> = creators.filter(|c| c.verified).collect();
+    let verified_shares: u128 = verified.iter().map(|c| c.share as u128).sum();
+
+    let mut paid: u64 = 0;
+    for (index, creator) in verified.iter().enumerate() {
+        let creator_account = accounts.next().ok_or(ErrorCode::MissingRoyaltyAccounts)?;
+        let share: u64 = if index + 1 == verified.len() {
+            royalty - paid
+        } else {
+            (royalty as u128 * creator.share as u128)
+                .checked_div(verified_shares)
+                .ok_or(ErrorCode::InvalidRoyaltyAmount)?
+                .try_into()
+                .map_err(|_| ErrorCode::InvalidRoyaltyAmount)?
+        };
+
+        match (args.payment_mint, payer_token_account) {
+            (Some(payment_mint), Some(payer_token_account)) => {
+                let token_account = Account::<TokenAccount>::try_from(creator_account)?;
+                require!(
+                    token_account.mint == payment_mint && token_account.owner == creator.address,
+                    ErrorCode::InvalidRoyaltyCreator
+                );
+                if share > 0 {
+                    invoke(
+                        &spl_token::instruction::transfer(
+                            &token_program.key(),
+                            &payer_token_account.key(),
+                            &creator_account.key(),
+                            &payer.key(),
+                            &[],
+                            share,
+                        )?,
+                        &[
+                            token_program.clone(),
+                            payer_token_account.clone(),
+                            creator_account.clone(),
+                            payer.clone(),
+                        ],
+                    )?;
+                }
+            }
+            _ => {
+                require_keys_eq!(
+                    creator_account.key(),
+                    creator.address,
+                    ErrorCode::InvalidRoyaltyCreator
+                );
+                if share > 0 {
+                    invoke(
+                        &system_instruction::transfer(&payer.key(), &creator_account.key(), share),
+                        &[payer.clone(), creator_account.clone(), system_program.clone()],
+                    )?;
+                }
+            }
+        }
+        paid = paid.checked_add(share).ok_or(ErrorCode::InvalidRoyaltyAmount)?;
+    }
+
+    msg!(format!("Paid {} in royalties", paid).as_str());
+    Ok(paid)
+}
@@ -0,0 +1,259 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+use mpl_token_metadata::instruction::DelegateArgs;
+use mpl_token_metadata::instruction::RevokeArgs;
+use mpl_token_metadata::state::TokenStandard;
+
+use crate::delegate::delegate_pnft;
+use crate::delegate::lock_pnft;
+use crate::delegate::revoke_pnft;
+use crate::delegate::unlock_pnft;
+use crate::delegate::PNFTAccounts;
+use crate::errors::ErrorCode;
+use crate::stake_authorization::is_stake_authorized;
+use crate::stake_entry::StakeEntry;
+use crate::stake_entry::StakeEntryKind;
+use crate::stake_pool::StakePool;
+use crate::utils::assert_metadata_for_mint;
+
+#[derive(Accounts)]
+pub struct StakePNFT<'info> {
+    #[account(mut)]
+    user: Signer<'info>,
+    #[account(mut)]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(mut,
+        constraint = stake_entry.pool == stake_pool.key() @ ErrorCode::InvalidStakePool,
+        constraint = stake_entry.original_mint == mint.key() @ ErrorCode::InvalidStakeEntry)]
+    stake_entry: Box<Account<'info, StakeEntry>>,
+    /// CHECK: This is not dangerous because we validate it in is_stake_authorized
+    #[account(seeds = [b"stake_authorization", stake_pool.key().as_ref(), mint.key().as_ref()], bump)]
+    stake_authorization_record: UncheckedAccount<'info>,
+    mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        token_account.owner == user.key() && token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    sysvar_instructions: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    authorization_rules_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> StakePNFT<'info> {
+    fn pnft_accounts(&self) -> PNFTAccounts<'info> {
+        PNFTAccounts {
+            token_owner: self.user.to_account_info(),
+            token_account: self.token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            metadata: self.metadata.to_account_info(),
+            edition: self.edition.to_account_info(),
+            token_record: self.token_record.to_account_info(),
+            payer: self.user.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            sysvar_instructions: self.sysvar_instructions.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            authorization_rules_program: self.authorization_rules_program.to_account_info(),
+            authorization_rules: self.authorization_rules.to_account_info(),
+            token_metadata_program: self.token_metadata_program.to_account_info(),
+        }
+    }
+}
+
+pub(crate) fn assert_can_stake(
+    stake_pool: &StakePool,
+    stake_entry: &StakeEntry,
+    stake_authorization_record: &AccountInfo,
+    now: i64,
+) -> Result<()> {
+    require!(!stake_pool.has_ended(now), ErrorCode::StakePoolHasEnded);
+    require!(!stake_entry.is_staked(), ErrorCode::StakeEntryAlreadyStaked);
+    require!(
+        !stake_pool.requires_authorization || is_stake_authorized(stake_authorization_record)?,
+        ErrorCode::InvalidStakeAuthorizationRecord
+    );
+    Ok(())
+}
+
+pub(crate) fn record_stake(
+    stake_pool: &mut StakePool,
+    stake_entry: &mut StakeEntry,
+    staker: Pubkey,
+    kind: StakeEntryKind,
+    now: i64,
+) {
+    stake_entry.amount = 1;
+    stake_entry.kind = kind;
+    stake_entry.last_staker = staker;
+    stake_entry.last_staked_at = now;
+    stake_entry.last_updated_at = now;
+    stake_entry.total_stake_seconds = 0;
+    stake_entry.cooldown_start_seconds = None;
+
+    stake_pool.total_staked = stake_pool.total_staked.checked_add(1).unwrap();
+}
+
+/// Validates an unstake by `user`. Starts the pool cooldown on the first call and
+/// returns whether the token can be released now.
+pub(crate) fn request_unstake(
+    stake_pool: &StakePool,
+    stake_entry: &mut StakeEntry,
+    user: Pubkey,
+    now: i64,
+) -> Result<bool> {
+    require!(stake_entry.is_staked(), ErrorCode::InvalidStakeEntry);
+    require_keys_eq!(stake_entry.last_staker, user, ErrorCode::InvalidUnstakeUser);
+    require!(
+        stake_entry.grouping_info.is_none(),
+        ErrorCode::GroupedStakeEntry
+    );
+    if let Some(min_stake_seconds) = stake_pool.min_stake_seconds {
+        require!(
+            stake_pool.has_ended(now)
+                || stake_entry.cooldown_start_seconds.is_some()
+                || now - stake_entry.last_staked_at >= min_stake_seconds as i64,
+            ErrorCode::MinStakeSecondsNotSatisfied
+        );
+    }
+
+    let cooldown_seconds = stake_pool.cooldown_seconds.unwrap_or(0) as i64;
+    if cooldown_seconds > 0 {
+        match stake_entry.cooldown_start_seconds {
+            None => {
+                msg!(format!("Starting {} second cooldown", cooldown_seconds).as_str());
+                stake_entry.update_total_stake_seconds(now);
+                stake_entry.cooldown_start_seconds = Some(now);
+                return Ok(false);
+            }
+            Some(cooldown_start_seconds) => require!(
+                now >= cooldown_start_seconds + cooldown_seconds,
+                ErrorCode::CooldownSecondRemaining
+            ),
+        }
+    }
+    Ok(true)
+}
+
+pub(crate) fn record_unstake(stake_pool: &mut StakePool, stake_entry: &mut StakeEntry, now: i64) {
+    stake_entry.update_total_stake_seconds(now);
+    stake_entry.amount = 0;
+    stake_entry.last_staker = Pubkey::default();
+    stake_entry.cooldown_start_seconds = None;
+
+    stake_pool.total_staked = stake_pool.total_staked.saturating_sub(1);
+}
+
+pub fn stake(ctx: Context<StakePNFT>) -> Result<()> {
+    msg!(format!("Staking pNFT {}", ctx.accounts.mint.key()).as_str());
+
+    let now = Clock::get()?.unix_timestamp;
+    assert_can_stake(
+        &ctx.accounts.stake_pool,
+        &ctx.accounts.stake_entry,
+        &ctx.accounts.stake_authorization_record,
+        now,
+    )?;
+
+    let metadata = assert_metadata_for_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.key(),
+    )?;
+    require!(
+        metadata.token_standard == Some(TokenStandard::ProgrammableNonFungible),
+        ErrorCode::InvalidStakeType
+    );
+
+    let accounts = ctx.accounts.pnft_accounts();
+    let stake_entry_info = ctx.accounts.stake_entry.to_account_info();
+
+    delegate_pnft(
+        &accounts,
+        &stake_entry_info,
+        &ctx.accounts.user.to_account_info(),
+        DelegateArgs::StakingV1 {
+            amount: 1,
+            authorization_data: None,
+        },
+        &[],
+    )?;
+    lock_pnft(
+        &accounts,
+        &stake_entry_info,
+        &[&[
+            b"stake_entry",
+            ctx.accounts.stake_entry.pool.as_ref(),
+            ctx.accounts.stake_entry.original_mint.as_ref(),
+            &[ctx.accounts.stake_entry.bump],
+        ]],
+    )?;
+
+    record_stake(
+        &mut ctx.accounts.stake_pool,
+        &mut ctx.accounts.stake_entry,
+        ctx.accounts.user.key(),
+        StakeEntryKind::Locked,
+        now,
+    );
+
+    Ok(())
+}
+
+pub fn unstake(ctx: Context<StakePNFT>) -> Result<()> {
+    msg!(format!("Unstaking pNFT {}", ctx.accounts.mint.key()).as_str());
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        ctx.accounts.stake_entry.kind == StakeEntryKind::Locked,
+        ErrorCode::InvalidStakeType
+    );
+    if !request_unstake(
+        &ctx.accounts.stake_pool,
+        &mut ctx.accounts.stake_entry,
+        ctx.accounts.user.key(),
+        now,
+    )? {
+        return Ok(());
+    }
+
+    let accounts = ctx.accounts.pnft_accounts();
+    let stake_entry_info = ctx.accounts.stake_entry.to_account_info();
+
+    unlock_pnft(
+        &accounts,
+        &stake_entry_info,
+        &[&[
+            b"stake_entry",
+            ctx.accounts.stake_entry.pool.as_ref(),
+            ctx.accounts.stake_entry.original_mint.as_ref(),
+            &[ctx.accounts.stake_entry.bump],
+        ]],
+    )?;
+    revoke_pnft(
+        &accounts,
+        &stake_entry_info,
+        &ctx.accounts.user.to_account_info(),
+        RevokeArgs::StakingV1,
+        &[],
+    )?;
+
+    record_unstake(&mut ctx.accounts.stake_pool, &mut ctx.accounts.stake_entry, now);
+
+    Ok(())
+}
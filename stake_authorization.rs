@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::stake_pool::StakePool;
+
+pub const STAKE_AUTHORIZATION_RECORD_SIZE: usize = 8 + 1 + 32 + 32;
+
+#[account]
+pub struct StakeAuthorizationRecord {
+    pub bump: u8,
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct AuthorizeMint<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = STAKE_AUTHORIZATION_RECORD_SIZE,
+        seeds = [b"stake_authorization", stake_pool.key().as_ref(), mint.as_ref()],
+        bump,
+    )]
+    pub stake_authorization_record: Box<Account<'info, StakeAuthorizationRecord>>,
+    #[account(has_one = authority @ ErrorCode::InvalidPoolAuthority)]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeauthorizeMint<'info> {
+    #[account(mut, close = authority, constraint =
+        stake_authorization_record.pool == stake_pool.key()
+        @ ErrorCode::InvalidStakeAuthorizationRecord)]
+    pub stake_authorization_record: Box<Account<'info, StakeAuthorizationRecord>>,
+    #[account(has_one = authority @ ErrorCode::InvalidPoolAuthority)]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Whether `record` is an existing authorization record. Callers constrain its
+/// address to `[b"stake_authorization", pool, mint]`.
+pub fn is_stake_authorized(record: &AccountInfo) -> Result<bool> {
+    if *record.owner != crate::id() || record.data_is_empty() {
+        return Ok(false);
+    }
+    Account::<StakeAuthorizationRecord>::try_from(record)?;
+    Ok(true)
+}
+
+pub fn authorize_mint(ctx: Context<AuthorizeMint>, mint: Pubkey) -> Result<()> {
+    let stake_authorization_record = &mut ctx.accounts.stake_authorization_record;
+    stake_authorization_record.bump = *ctx.bumps.get("stake_authorization_record").unwrap();
+    stake_authorization_record.pool = ctx.accounts.stake_pool.key();
+    stake_authorization_record.mint = mint;
+
+    Ok(())
+}
+
+pub fn deauthorize_mint(_ctx: Context<DeauthorizeMint>) -> Result<()> {
+    Ok(())
+}
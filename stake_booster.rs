@@ -0,0 +1,185 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::errors::ErrorCode;
+use crate::stake_entry::StakeEntry;
+use crate::stake_pool::StakePool;
+
+pub const STAKE_BOOSTER_SIZE: usize = 8 + 1 + 32 + 8 + 8 + 32 + 32 + 16 + 8;
+
+#[account]
+pub struct StakeBooster {
+    pub bump: u8,
+    pub stake_pool: Pubkey,
+    pub identifier: u64,
+    /// Price of `boost_seconds` of stake time
+    pub payment_amount: u64,
+    pub payment_mint: Pubkey,
+    /// Owner of the token account boost payments are sent to
+    pub payment_manager: Pubkey,
+    pub boost_seconds: u128,
+    pub start_time_seconds: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct StakeBoosterArgs {
+    pub payment_amount: u64,
+    pub payment_mint: Pubkey,
+    pub payment_manager: Pubkey,
+    pub boost_seconds: u128,
+    pub start_time_seconds: i64,
+}
+
+#[derive(Accounts)]
+#[instruction(identifier: u64)]
+pub struct InitStakeBooster<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = STAKE_BOOSTER_SIZE,
+        seeds = [b"stake_booster", stake_pool.key().as_ref(), &identifier.to_le_bytes()],
+        bump,
+    )]
+    pub stake_booster: Box<Account<'info, StakeBooster>>,
+    #[account(has_one = authority @ ErrorCode::InvalidPoolAuthority)]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStakeBooster<'info> {
+    #[account(mut, constraint =
+        stake_booster.stake_pool == stake_pool.key()
+        @ ErrorCode::InvalidStakePool)]
+    pub stake_booster: Box<Account<'info, StakeBooster>>,
+    #[account(has_one = authority @ ErrorCode::InvalidPoolAuthority)]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BoostStakeEntry<'info> {
+    #[account(constraint =
+        stake_booster.stake_pool == stake_entry.pool
+        @ ErrorCode::InvalidStakePool)]
+    pub stake_booster: Box<Account<'info, StakeBooster>>,
+    #[account(mut, constraint =
+        stake_entry.original_mint == original_mint.key()
+        @ ErrorCode::InvalidOriginalMint)]
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
+    pub original_mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        payer_token_account.owner == payer.key()
+        && payer_token_account.mint == stake_booster.payment_mint
+        @ ErrorCode::InvalidBoostPayerTokenAccount)]
+    pub payer_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, constraint =
+        payment_recipient_token_account.owner == payment_manager.key()
+        && payment_recipient_token_account.mint == stake_booster.payment_mint
+        @ ErrorCode::InvalidBoostPaymentRecipientTokenAccount)]
+    pub payment_recipient_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we only compare its key against the booster
+    #[account(address = stake_booster.payment_manager @ ErrorCode::InvalidPaymentManager)]
+    pub payment_manager: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn init_stake_booster(
+    ctx: Context<InitStakeBooster>,
+    identifier: u64,
+    args: StakeBoosterArgs,
+) -> Result<()> {
+    require!(args.boost_seconds > 0, ErrorCode::InvalidStakePool);
+
+    let stake_booster = &mut ctx.accounts.stake_booster;
+    stake_booster.bump = *ctx.bumps.get("stake_booster").unwrap();
+    stake_booster.stake_pool = ctx.accounts.stake_pool.key();
+    stake_booster.identifier = identifier;
+    stake_booster.payment_amount = args.payment_amount;
+    stake_booster.payment_mint = args.payment_mint;
+    stake_booster.payment_manager = args.payment_manager;
+    stake_booster.boost_seconds = args.boost_seconds;
+    stake_booster.start_time_seconds = args.start_time_seconds;
+
+    Ok(())
+}
+
+pub fn update_stake_booster(ctx: Context<UpdateStakeBooster>, args: StakeBoosterArgs) -> Result<()> {
+    require!(args.boost_seconds > 0, ErrorCode::InvalidStakePool);
+
+    let stake_booster = &mut ctx.accounts.stake_booster;
+    stake_booster.payment_amount = args.payment_amount;
+    stake_booster.payment_mint = args.payment_mint;
+    stake_booster.payment_manager = args.payment_manager;
+    stake_booster.boost_seconds = args.boost_seconds;
+    stake_booster.start_time_seconds = args.start_time_seconds;
+
+    Ok(())
+}
+
+pub fn boost_stake_entry(ctx: Context<BoostStakeEntry>, seconds_to_boost: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let stake_booster = &ctx.accounts.stake_booster;
+    let stake_entry = &mut ctx.accounts.stake_entry;
+
+    require!(
+        stake_entry.is_staked() && stake_entry.cooldown_start_seconds.is_none(),
+        ErrorCode::CannotBoostUnstakedToken
+    );
+    require!(
+        ctx.accounts.original_mint.supply <= 1,
+        ErrorCode::CannotBoostFungibleToken
+    );
+
+    // total_stake_seconds is reset by every stake, so it only holds the seconds accrued
+    // (and boosted) since last_staked_at and the cap cannot borrow a previous staker's time
+    stake_entry.update_total_stake_seconds(now);
+    let boosted_total = stake_entry
+        .total_stake_seconds
+        .checked_add(seconds_to_boost as u128)
+        .ok_or(ErrorCode::CannotBoostMoreThanCurrentTime)?;
+    require!(
+        now >= stake_booster.start_time_seconds
+            && boosted_total <= (now - stake_entry.last_staked_at) as u128,
+        ErrorCode::CannotBoostMoreThanCurrentTime
+    );
+
+    // Rounded up so small boosts are never free
+    let payment_amount: u64 = (seconds_to_boost as u128)
+        .checked_mul(stake_booster.payment_amount as u128)
+        .and_then(|amount| amount.checked_add(stake_booster.boost_seconds - 1))
+        .map(|amount| amount / stake_booster.boost_seconds)
+        .and_then(|amount| amount.try_into().ok())
+        .ok_or(ErrorCode::InvalidBoostPaymentAmount)?;
+
+    msg!(format!("Boosting {} seconds for {}", seconds_to_boost, payment_amount).as_str());
+
+    invoke(
+        &spl_token::instruction::transfer(
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.payer_token_account.key(),
+            &ctx.accounts.payment_recipient_token_account.key(),
+            &ctx.accounts.payer.key(),
+            &[],
+            payment_amount,
+        )?,
+        &[
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.payer_token_account.to_account_info(),
+            ctx.accounts.payment_recipient_token_account.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+        ],
+    )?;
+
+    stake_entry.total_stake_seconds = boosted_total;
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::errors::ErrorCode;
+use crate::stake_authorization::is_stake_authorized;
+use crate::stake_pool::StakePool;
+use crate::utils::assert_metadata_for_mint;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StakeEntryKind {
+    /// Token stays in the staker wallet under a staking delegate and lock
+    Locked,
+    /// Token is held by the stake entry and the staker holds a receipt
+    Custodial,
+}
+
+pub const STAKE_ENTRY_SIZE: usize = 8 + 1 + 32 + 8 + 32 + 1 + 32 + 8 + 8 + 16 + (1 + 8) + (1 + 32) + (1 + 32);
+
+#[account]
+pub struct StakeEntry {
+    pub bump: u8,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub original_mint: Pubkey,
+    pub kind: StakeEntryKind,
+    /// Current staker, `Pubkey::default()` while unstaked
+    pub last_staker: Pubkey,
+    pub last_staked_at: i64,
+    pub last_updated_at: i64,
+    /// Seconds staked (plus boosts) since `last_staked_at`, reset on every stake
+    pub total_stake_seconds: u128,
+    /// Set by the first `unstake` call when the pool has a cooldown
+    pub cooldown_start_seconds: Option<i64>,
+    /// Group this entry currently belongs to
+    pub grouping_info: Option<Pubkey>,
+    /// Receipt mint handed to custodial stakers
+    pub stake_mint: Option<Pubkey>,
+}
+
+impl StakeEntry {
+    pub fn is_staked(&self) -> bool {
+        self.last_staker != Pubkey::default()
+    }
+
+    /// Adds the seconds staked since the last update to `total_stake_seconds`.
+    /// Time spent cooling down does not count as staked.
+    pub fn update_total_stake_seconds(&mut self, now: i64) {
+        let staked_until = self.cooldown_start_seconds.map_or(now, |start| start.min(now));
+        if self.is_staked() && staked_until > self.last_updated_at {
+            self.total_stake_seconds = self
+                .total_stake_seconds
+                .saturating_add((staked_until - self.last_updated_at) as u128);
+            self.last_updated_at = staked_until;
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitEntry<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = STAKE_ENTRY_SIZE,
+        seeds = [b"stake_entry", stake_pool.key().as_ref(), original_mint.key().as_ref()],
+        bump,
+    )]
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
+    pub stake_pool: Box<Account<'info, StakePool>>,
+    pub original_mint: Box<Account<'info, Mint>>,
+    /// CHECK: This is not dangerous because we validate it in assert_metadata_for_mint
+    pub original_mint_metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in is_stake_authorized
+    #[account(seeds = [b"stake_authorization", stake_pool.key().as_ref(), original_mint.key().as_ref()], bump)]
+    pub stake_authorization_record: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEntry<'info> {
+    #[account(
+        mut,
+        close = authority,
+        constraint = stake_entry.pool == stake_pool.key() @ ErrorCode::InvalidStakePool,
+        constraint = !stake_entry.is_staked() @ ErrorCode::CannotCloseStakedEntry,
+    )]
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
+    #[account(has_one = authority @ ErrorCode::InvalidPoolAuthority)]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub fn init_entry(ctx: Context<InitEntry>) -> Result<()> {
+    let stake_pool = &ctx.accounts.stake_pool;
+    let metadata = assert_metadata_for_mint(
+        &ctx.accounts.original_mint_metadata.to_account_info(),
+        &ctx.accounts.original_mint.key(),
+    )?;
+
+    let allowed_collection = stake_pool.requires_collections.is_empty()
+        || metadata.collection.as_ref().map_or(false, |collection| {
+            collection.verified && stake_pool.requires_collections.contains(&collection.key)
+        });
+    let allowed_creator = stake_pool.requires_creators.is_empty()
+        || metadata.data.creators.iter().flatten().any(|creator| {
+            creator.verified && stake_pool.requires_creators.contains(&creator.address)
+        });
+    let authorized = is_stake_authorized(&ctx.accounts.stake_authorization_record)?;
+    require!(
+        authorized || !stake_pool.requires_authorization,
+        ErrorCode::InvalidStakeAuthorizationRecord
+    );
+    require!(
+        allowed_collection && allowed_creator,
+        ErrorCode::MintNotAllowedInPool
+    );
+
+    let stake_entry = &mut ctx.accounts.stake_entry;
+    stake_entry.bump = *ctx.bumps.get("stake_entry").unwrap();
+    stake_entry.pool = stake_pool.key();
+    stake_entry.amount = 0;
+    stake_entry.original_mint = ctx.accounts.original_mint.key();
+    stake_entry.kind = StakeEntryKind::Locked;
+    stake_entry.last_staker = Pubkey::default();
+    stake_entry.last_staked_at = 0;
+    stake_entry.last_updated_at = 0;
+    stake_entry.total_stake_seconds = 0;
+    stake_entry.cooldown_start_seconds = None;
+    stake_entry.grouping_info = None;
+    stake_entry.stake_mint = None;
+
+    Ok(())
+}
+
+pub fn close_entry(_ctx: Context<CloseEntry>) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::errors::ErrorCode;
+use crate::stake_entry::StakeEntry;
+
+/// Funds are held in token accounts owned by the stake entry PDA, usually its
+/// associated token account for the funds mint, so they can receive airdrops
+/// addressed to staked tokens. The staked mint itself can never be claimed.
+#[derive(Accounts)]
+pub struct ClaimStakeEntryFunds<'info> {
+    #[account(constraint =
+        stake_entry.last_staker == staker.key()
+        @ ErrorCode::InvalidLastStaker)]
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
+    #[account(constraint =
+        funds_mint.key() != stake_entry.original_mint
+        @ ErrorCode::InvalidFundsMint)]
+    pub funds_mint: Box<Account<'info, Mint>>,
+    #[account(mut,
+        constraint = stake_entry_funds_token_account.owner == stake_entry.key() @ ErrorCode::InvalidStakeEntry,
+        constraint = stake_entry_funds_token_account.mint == funds_mint.key() @ ErrorCode::InvalidFundsMint)]
+    pub stake_entry_funds_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut,
+        constraint = user_funds_token_account.owner == staker.key() @ ErrorCode::InvalidAuthority,
+        constraint = user_funds_token_account.mint == funds_mint.key() @ ErrorCode::InvalidMintForTokenAccount)]
+    pub user_funds_token_account: Box<Account<'info, TokenAccount>>,
+    pub staker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn claim_stake_entry_funds(ctx: Context<ClaimStakeEntryFunds>) -> Result<()> {
+    let amount = ctx.accounts.stake_entry_funds_token_account.amount;
+    require!(amount > 0, ErrorCode::StakeEntryFundsTokenAccountEmpty);
+
+    msg!(format!("Claiming {} of {}", amount, ctx.accounts.funds_mint.key()).as_str());
+
+    let stake_entry = &ctx.accounts.stake_entry;
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.stake_entry_funds_token_account.key(),
+            &ctx.accounts.user_funds_token_account.key(),
+            &stake_entry.key(),
+            &[],
+            amount,
+        )?,
+        &[
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.stake_entry_funds_token_account.to_account_info(),
+            ctx.accounts.user_funds_token_account.to_account_info(),
+            stake_entry.to_account_info(),
+        ],
+        &[&[
+            b"stake_entry",
+            stake_entry.pool.as_ref(),
+            stake_entry.original_mint.as_ref(),
+            &[stake_entry.bump],
+        ]],
+    )?;
+
+    Ok(())
+}
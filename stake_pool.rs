@@ -0,0 +1,149 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+
+pub const STAKE_POOL_MAX_REQUIREMENTS: usize = 5;
+pub const STAKE_POOL_SIZE: usize = 8
+    + 1
+    + 8
+    + 32
+    + (4 + 32 * STAKE_POOL_MAX_REQUIREMENTS)
+    + (4 + 32 * STAKE_POOL_MAX_REQUIREMENTS)
+    + 1
+    + (1 + 4)
+    + (1 + 4)
+    + (1 + 8)
+    + 4
+    + 4;
+
+#[account]
+pub struct StakePool {
+    pub bump: u8,
+    pub identifier: u64,
+    pub authority: Pubkey,
+    /// Verified collections a mint may belong to, empty allows any collection
+    pub requires_collections: Vec<Pubkey>,
+    /// Verified creators a mint may list, empty allows any creator
+    pub requires_creators: Vec<Pubkey>,
+    /// Only mints with a `StakeAuthorizationRecord` may be staked
+    pub requires_authorization: bool,
+    pub cooldown_seconds: Option<u32>,
+    pub min_stake_seconds: Option<u32>,
+    pub end_date: Option<i64>,
+    pub total_staked: u32,
+    /// Entries must stay grouped this long before they can be removed
+    pub min_group_seconds: u32,
+}
+
+impl StakePool {
+    pub fn has_ended(&self, now: i64) -> bool {
+        self.end_date.map_or(false, |end_date| now >= end_date)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct InitPoolArgs {
+    pub identifier: u64,
+    pub requires_collections: Vec<Pubkey>,
+    pub requires_creators: Vec<Pubkey>,
+    pub requires_authorization: bool,
+    pub cooldown_seconds: Option<u32>,
+    pub min_stake_seconds: Option<u32>,
+    pub end_date: Option<i64>,
+    pub min_group_seconds: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UpdatePoolArgs {
+    pub authority: Pubkey,
+    pub requires_collections: Vec<Pubkey>,
+    pub requires_creators: Vec<Pubkey>,
+    pub requires_authorization: bool,
+    pub cooldown_seconds: Option<u32>,
+    pub min_stake_seconds: Option<u32>,
+    pub end_date: Option<i64>,
+    pub min_group_seconds: u32,
+}
+
+#[derive(Accounts)]
+#[instruction(args: InitPoolArgs)]
+pub struct InitPool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = STAKE_POOL_SIZE,
+        seeds = [b"stake_pool", authority.key().as_ref(), &args.identifier.to_le_bytes()],
+        bump,
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePool<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::InvalidPoolAuthority)]
+    pub stake_pool: Account<'info, StakePool>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(
+        mut,
+        close = authority,
+        has_one = authority @ ErrorCode::InvalidPoolAuthority,
+        constraint = stake_pool.total_staked == 0 @ ErrorCode::CannotClosePoolWithStakedEntries,
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub fn init_pool(ctx: Context<InitPool>, args: InitPoolArgs) -> Result<()> {
+    require!(
+        args.requires_collections.len() <= STAKE_POOL_MAX_REQUIREMENTS
+            && args.requires_creators.len() <= STAKE_POOL_MAX_REQUIREMENTS,
+        ErrorCode::InvalidStakePool
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.bump = *ctx.bumps.get("stake_pool").unwrap();
+    stake_pool.identifier = args.identifier;
+    stake_pool.authority = ctx.accounts.authority.key();
+    stake_pool.requires_collections = args.requires_collections;
+    stake_pool.requires_creators = args.requires_creators;
+    stake_pool.requires_authorization = args.requires_authorization;
+    stake_pool.cooldown_seconds = args.cooldown_seconds;
+    stake_pool.min_stake_seconds = args.min_stake_seconds;
+    stake_pool.end_date = args.end_date;
+    stake_pool.total_staked = 0;
+    stake_pool.min_group_seconds = args.min_group_seconds;
+
+    Ok(())
+}
+
+pub fn update_pool(ctx: Context<UpdatePool>, args: UpdatePoolArgs) -> Result<()> {
+    require!(
+        args.requires_collections.len() <= STAKE_POOL_MAX_REQUIREMENTS
+            && args.requires_creators.len() <= STAKE_POOL_MAX_REQUIREMENTS,
+        ErrorCode::InvalidStakePool
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.authority = args.authority;
+    stake_pool.requires_collections = args.requires_collections;
+    stake_pool.requires_creators = args.requires_creators;
+    stake_pool.requires_authorization = args.requires_authorization;
+    stake_pool.cooldown_seconds = args.cooldown_seconds;
+    stake_pool.min_stake_seconds = args.min_stake_seconds;
+    stake_pool.end_date = args.end_date;
+    stake_pool.min_group_seconds = args.min_group_seconds;
+
+    Ok(())
+}
+
+pub fn close_pool(_ctx: Context<ClosePool>) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,391 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+use mpl_token_metadata::state::TokenStandard;
+
+use crate::errors::ErrorCode;
+use crate::pnft::invoke_transfer_pnft;
+use crate::pnft::PNFTTransferAccounts;
+use crate::stake::assert_can_stake;
+use crate::stake::record_stake;
+use crate::stake::record_unstake;
+use crate::stake::request_unstake;
+use crate::stake_entry::StakeEntry;
+use crate::stake_entry::StakeEntryKind;
+use crate::stake_pool::StakePool;
+use crate::utils::assert_metadata_for_mint;
+
+#[derive(Accounts)]
+pub struct InitStakeMint<'info> {
+    #[account(mut, constraint =
+        stake_entry.original_mint == original_mint.key()
+        @ ErrorCode::InvalidStakeEntry)]
+    stake_entry: Box<Account<'info, StakeEntry>>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"stake_mint", stake_entry.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = stake_entry,
+        mint::freeze_authority = stake_entry,
+    )]
+    stake_mint: Box<Account<'info, Mint>>,
+    original_mint: Box<Account<'info, Mint>>,
+    /// CHECK: This is not dangerous because we validate it in assert_metadata_for_mint
+    original_mint_metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because it is created by the token metadata program
+    #[account(mut)]
+    stake_mint_metadata: UncheckedAccount<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    token_program: Program<'info, Token>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StakeCustodial<'info> {
+    #[account(mut)]
+    user: Signer<'info>,
+    #[account(mut)]
+    stake_pool: Box<Account<'info, StakePool>>,
+    #[account(mut,
+        constraint = stake_entry.pool == stake_pool.key() @ ErrorCode::InvalidStakePool,
+        constraint = stake_entry.original_mint == mint.key() @ ErrorCode::InvalidStakeEntry)]
+    stake_entry: Box<Account<'info, StakeEntry>>,
+    /// CHECK: This is not dangerous because we validate it in is_stake_authorized
+    #[account(seeds = [b"stake_authorization", stake_pool.key().as_ref(), mint.key().as_ref()], bump)]
+    stake_authorization_record: UncheckedAccount<'info>,
+    mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        user_token_account.owner == user.key() && user_token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    user_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, constraint =
+        stake_entry_token_account.owner == stake_entry.key()
+        && stake_entry_token_account.mint == mint.key()
+        @ ErrorCode::InvalidStakeEntryOriginalMintTokenAccount)]
+    stake_entry_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, constraint =
+        stake_entry.stake_mint == Some(stake_mint.key())
+        @ ErrorCode::InvalidReceiptMint)]
+    stake_mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        user_stake_mint_token_account.owner == user.key()
+        && user_stake_mint_token_account.mint == stake_mint.key()
+        @ ErrorCode::InvalidUserMintTokenAccount)]
+    user_stake_mint_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we validate it in assert_metadata_for_mint
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    user_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    stake_entry_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    sysvar_instructions: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    authorization_rules_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> StakeCustodial<'info> {
+    fn transfer_accounts(&self, into_stake_entry: bool) -> PNFTTransferAccounts<'info> {
+        let (source, owner, source_record, destination, recipient, destination_record) =
+            if into_stake_entry {
+                (
+                    &self.user_token_account,
+                    self.user.to_account_info(),
+                    &self.user_token_record,
+                    &self.stake_entry_token_account,
+                    self.stake_entry.to_account_info(),
+                    &self.stake_entry_token_record,
+                )
+            } else {
+                (
+                    &self.stake_entry_token_account,
+                    self.stake_entry.to_account_info(),
+                    &self.stake_entry_token_record,
+                    &self.user_token_account,
+                    self.user.to_account_info(),
+                    &self.user_token_record,
+                )
+            };
+
+        PNFTTransferAccounts {
+            token_account: source.to_account_info(),
+            token_owner: owner.clone(),
+            destination_token_account: destination.to_account_info(),
+            destination: recipient,
+            mint: self.mint.to_account_info(),
+            metadata: self.metadata.to_account_info(),
+            edition: self.edition.to_account_info(),
+            token_record: source_record.to_account_info(),
+            destination_token_record: destination_record.to_account_info(),
+            authority: owner,
+            payer: self.user.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            sysvar_instructions: self.sysvar_instructions.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            associated_token_program: self.associated_token_program.to_account_info(),
+            authorization_rules_program: self.authorization_rules_program.to_account_info(),
+            authorization_rules: self.authorization_rules.to_account_info(),
+        }
+    }
+}
+
+pub fn init_stake_mint(ctx: Context<InitStakeMint>) -> Result<()> {
+    require!(
+        ctx.accounts.stake_entry.stake_mint.is_none(),
+        ErrorCode::StakeMintAlreadyInitialized
+    );
+
+    let original_metadata = assert_metadata_for_mint(
+        &ctx.accounts.original_mint_metadata.to_account_info(),
+        &ctx.accounts.original_mint.key(),
+    )?;
+    let mut name = format!(
+        "STAKED {}",
+        original_metadata.data.name.trim_matches(char::from(0))
+    );
+    while name.len() > mpl_token_metadata::state::MAX_NAME_LENGTH {
+        name.pop();
+    }
+
+    let stake_entry = &ctx.accounts.stake_entry;
+    invoke_signed(
+        &mpl_token_metadata::instruction::create_metadata_accounts_v3(
+            mpl_token_metadata::id(),
+            ctx.accounts.stake_mint_metadata.key(),
+            ctx.accounts.stake_mint.key(),
+            stake_entry.key(),
+            ctx.accounts.payer.key(),
+            stake_entry.key(),
+            name,
+            "STAKED".to_string(),
+            original_metadata.data.uri.trim_matches(char::from(0)).to_string(),
+            None,
+            0,
+            true,
+            true,
+            None,
+            None,
+            None,
+        ),
+        &[
+            ctx.accounts.stake_mint_metadata.to_account_info(),
+            ctx.accounts.stake_mint.to_account_info(),
+            stake_entry.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+        &[&[
+            b"stake_entry",
+            stake_entry.pool.as_ref(),
+            stake_entry.original_mint.as_ref(),
+            &[stake_entry.bump],
+        ]],
+    )?;
+
+    ctx.accounts.stake_entry.stake_mint = Some(ctx.accounts.stake_mint.key());
+
+    Ok(())
+}
+
+pub fn stake_custodial(ctx: Context<StakeCustodial>) -> Result<()> {
+    msg!(format!("Staking {} into stake entry", ctx.accounts.mint.key()).as_str());
+
+    let now = Clock::get()?.unix_timestamp;
+    assert_can_stake(
+        &ctx.accounts.stake_pool,
+        &ctx.accounts.stake_entry,
+        &ctx.accounts.stake_authorization_record,
+        now,
+    )?;
+
+    let metadata = assert_metadata_for_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.key(),
+    )?;
+    if metadata.token_standard == Some(TokenStandard::ProgrammableNonFungible) {
+        invoke_transfer_pnft(&ctx.accounts.transfer_accounts(true), &[])?;
+    } else {
+        invoke(
+            &spl_token::instruction::transfer(
+                &ctx.accounts.token_program.key(),
+                &ctx.accounts.user_token_account.key(),
+                &ctx.accounts.stake_entry_token_account.key(),
+                &ctx.accounts.user.key(),
+                &[],
+                1,
+            )?,
+            &[
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.user_token_account.to_account_info(),
+                ctx.accounts.stake_entry_token_account.to_account_info(),
+                ctx.accounts.user.to_account_info(),
+            ],
+        )?;
+    }
+
+    let stake_entry_seeds: &[&[u8]] = &[
+        b"stake_entry",
+        ctx.accounts.stake_entry.pool.as_ref(),
+        ctx.accounts.stake_entry.original_mint.as_ref(),
+        &[ctx.accounts.stake_entry.bump],
+    ];
+
+    // receipts stay frozen in the staker wallet so they cannot be transferred
+    invoke_signed(
+        &spl_token::instruction::mint_to(
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.stake_mint.key(),
+            &ctx.accounts.user_stake_mint_token_account.key(),
+            &ctx.accounts.stake_entry.key(),
+            &[],
+            1,
+        )?,
+        &[
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.stake_mint.to_account_info(),
+            ctx.accounts.user_stake_mint_token_account.to_account_info(),
+            ctx.accounts.stake_entry.to_account_info(),
+        ],
+        &[stake_entry_seeds],
+    )?;
+    invoke_signed(
+        &spl_token::instruction::freeze_account(
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.user_stake_mint_token_account.key(),
+            &ctx.accounts.stake_mint.key(),
+            &ctx.accounts.stake_entry.key(),
+            &[],
+        )?,
+        &[
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_stake_mint_token_account.to_account_info(),
+            ctx.accounts.stake_mint.to_account_info(),
+            ctx.accounts.stake_entry.to_account_info(),
+        ],
+        &[stake_entry_seeds],
+    )?;
+
+    record_stake(
+        &mut ctx.accounts.stake_pool,
+        &mut ctx.accounts.stake_entry,
+        ctx.accounts.user.key(),
+        StakeEntryKind::Custodial,
+        now,
+    );
+
+    Ok(())
+}
+
+pub fn unstake_custodial(ctx: Context<StakeCustodial>) -> Result<()> {
+    msg!(format!("Unstaking {} from stake entry", ctx.accounts.mint.key()).as_str());
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        ctx.accounts.stake_entry.kind == StakeEntryKind::Custodial,
+        ErrorCode::InvalidStakeType
+    );
+    if !request_unstake(
+        &ctx.accounts.stake_pool,
+        &mut ctx.accounts.stake_entry,
+        ctx.accounts.user.key(),
+        now,
+    )? {
+        return Ok(());
+    }
+
+    let stake_entry_pool = ctx.accounts.stake_entry.pool;
+    let stake_entry_mint = ctx.accounts.stake_entry.original_mint;
+    let stake_entry_seeds: &[&[u8]] = &[
+        b"stake_entry",
+        stake_entry_pool.as_ref(),
+        stake_entry_mint.as_ref(),
+        &[ctx.accounts.stake_entry.bump],
+    ];
+
+    invoke_signed(
+        &spl_token::instruction::thaw_account(
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.user_stake_mint_token_account.key(),
+            &ctx.accounts.stake_mint.key(),
+            &ctx.accounts.stake_entry.key(),
+            &[],
+        )?,
+        &[
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_stake_mint_token_account.to_account_info(),
+            ctx.accounts.stake_mint.to_account_info(),
+            ctx.accounts.stake_entry.to_account_info(),
+        ],
+        &[stake_entry_seeds],
+    )?;
+    invoke(
+        &spl_token::instruction::burn(
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.user_stake_mint_token_account.key(),
+            &ctx.accounts.stake_mint.key(),
+            &ctx.accounts.user.key(),
+            &[],
+            1,
+        )?,
+        &[
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.user_stake_mint_token_account.to_account_info(),
+            ctx.accounts.stake_mint.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+        ],
+    )?;
+
+    let metadata = assert_metadata_for_mint(
+        &ctx.accounts.metadata.to_account_info(),
+        &ctx.accounts.mint.key(),
+    )?;
+    if metadata.token_standard == Some(TokenStandard::ProgrammableNonFungible) {
+        invoke_transfer_pnft(&ctx.accounts.transfer_accounts(false), &[stake_entry_seeds])?;
+    } else {
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &ctx.accounts.token_program.key(),
+                &ctx.accounts.stake_entry_token_account.key(),
+                &ctx.accounts.user_token_account.key(),
+                &ctx.accounts.stake_entry.key(),
+                &[],
+                1,
+            )?,
+            &[
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.stake_entry_token_account.to_account_info(),
+                ctx.accounts.user_token_account.to_account_info(),
+                ctx.accounts.stake_entry.to_account_info(),
+            ],
+            &[stake_entry_seeds],
+        )?;
+    }
+
+    record_unstake(&mut ctx.accounts.stake_pool, &mut ctx.accounts.stake_entry, now);
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+
+use crate::errors::DreamersError;
+
+/// Layout version of `DreamerState` and `SwappedState`, bumped whenever a field
+/// is appended. Handlers reject older accounts until `migrate_state` runs.
+pub const STATE_VERSION: u8 = 1;
+pub const DREAMER_STATE_SIZE: usize = 8 + 1 + 1 + 32 + 1;
+pub const SWAPPED_STATE_SIZE: usize = 8 + 1 + 32 + 1;
+
+/// Version byte at the end of a state account. Accounts created before it was
+/// added have no byte left to read and load as version 0.
+#[derive(AnchorSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StateVersion(pub u8);
+
+impl StateVersion {
+    pub fn is_current(&self) -> bool {
+        self.0 == STATE_VERSION
+    }
+}
+
+impl AnchorDeserialize for StateVersion {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut version = [0u8; 1];
+        match reader.read(&mut version)? {
+            0 => Ok(StateVersion(0)),
+            _ => Ok(StateVersion(version[0])),
+        }
+    }
+}
+
+#[account]
+pub struct DreamerState {
+    pub bump: u8,
+    pub id: u8,
+    pub authority: Pubkey,
+    pub version: StateVersion,
+}
+
+#[account]
+pub struct SwappedState {
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub version: StateVersion,
+}
+
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: This is not dangerous because we check its discriminator in migrate_state
+    #[account(mut, owner = crate::id())]
+    pub state: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Grows a dreamer or swapped PDA to the current layout and stamps
+/// `STATE_VERSION`. Appended fields start zeroed and the payer tops up the rent.
+/// Anyone can pay, so users are never stuck behind an unmigrated state.
+pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+    let state = ctx.accounts.state.to_account_info();
+    let is_dreamer = {
+        let data = state.try_borrow_data()?;
+        if data.starts_with(&DreamerState::discriminator()) {
+            true
+        } else if data.starts_with(&SwappedState::discriminator()) {
+            false
+        } else {
+            return err!(DreamersError::AccountNotMatch);
+        }
+    };
+    let size = if is_dreamer {
+        DREAMER_STATE_SIZE
+    } else {
+        SWAPPED_STATE_SIZE
+    };
+
+    if state.data_len() < size {
+        let rent = Rent::get()?
+            .minimum_balance(size)
+            .saturating_sub(state.lamports());
+        if rent > 0 {
+            invoke(
+                &system_instruction::transfer(&ctx.accounts.payer.key(), &state.key(), rent),
+                &[
+                    ctx.accounts.payer.to_account_info(),
+                    state.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+        state.realloc(size, true)?;
+    }
+
+    let mut data = state.try_borrow_mut_data()?;
+    if is_dreamer {
+        let mut dreamer = DreamerState::try_deserialize(&mut &data[..])?;
+        dreamer.version = StateVersion(STATE_VERSION);
+        dreamer.try_serialize(&mut &mut data[..])?;
+    } else {
+        let mut swapped = SwappedState::try_deserialize(&mut &data[..])?;
+        swapped.version = StateVersion(STATE_VERSION);
+        swapped.try_serialize(&mut &mut data[..])?;
+    }
+
+    msg!(format!("Migrated state to version {}", STATE_VERSION).as_str());
+    Ok(())
+}
@@ -0,0 +1,408 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+
+use crate::deposit::DepositRecord;
+use crate::deposit::DEPOSIT_RECORD_SIZE;
+use crate::errors::DreamersError;
+use crate::errors::ErrorCode;
+use crate::state::DreamerState;
+use crate::state::SwappedState;
+
+pub const STATE_CONFIG_SIZE: usize =
+    8 + 1 + 32 + 1 + (1 + 32) + 8 + (1 + 32) + 8 + 2 + (1 + 4) + 4 + 8 + 4 + 4 + 4;
+pub const ROOT_CONFIG_SIZE: usize = 8 + 1 + 32;
+
+/// Program-wide admin, separate from the state authorities. Only `admin` can
+/// change the rate limits of dreamer and swapped PDAs.
+#[account]
+pub struct RootConfig {
+    pub bump: u8,
+    pub admin: Pubkey,
+}
+
+/// Optional policies for a dreamer or swapped PDA, kept beside the state so the
+/// state layout does not change. Handlers treat a missing config as defaults.
+#[account]
+pub struct StateConfig {
+    pub bump: u8,
+    pub state: Pubkey,
+    /// Direct transfers out of the state may only go to the depositor or an approved
+    /// destination. Merkle claims, raffle prizes and vesting payouts pick their
+    /// recipient by other means and are not restricted.
+    pub restrict_to_depositor: bool,
+    /// Merkle root of wallets allowed to receive rewards, `None` allows any wallet
+    pub allowlist_root: Option<[u8; 32]>,
+    pub allowlist_round: u64,
+    /// Merkle root of (wallet, mint) pairs winners may claim themselves
+    pub claim_root: Option<[u8; 32]>,
+    pub claim_round: u64,
+    /// Share of each sale on a swapped PDA paid to its authority, in basis points.
+    /// Listings keep the value set when they were created.
+    pub listing_fee_bps: u16,
+    /// Most transfers out of the state per window, `None` disables the limit
+    pub max_transfers_per_window: Option<u32>,
+    pub rate_limit_window_seconds: u32,
+    pub window_started_at: i64,
+    pub transfers_in_window: u32,
+    /// Deposit records of the state with `held` set
+    pub held_assets: u32,
+    /// Raffles, auctions, listings and vesting schedules of the state not yet closed
+    pub open_positions: u32,
+}
+
+impl StateConfig {
+    /// Loads the config stored at `info`, `None` if it was never created.
+    pub fn load(info: &AccountInfo) -> Result<Option<StateConfig>> {
+        if *info.owner != crate::id() || info.data_is_empty() {
+            return Ok(None);
+        }
+        let data = info.try_borrow_data()?;
+        Ok(Some(StateConfig::try_deserialize(&mut &data[..])?))
+    }
+
+    /// Counts one transfer out of the state against the rate limit, starting a
+    /// new window once the current one has passed.
+    pub fn consume_transfer(&mut self, now: i64) -> Result<()> {
+        let max_transfers = match self.max_transfers_per_window {
+            Some(max_transfers) => max_transfers,
+            None => return Ok(()),
+        };
+        if now >= self.window_started_at + self.rate_limit_window_seconds as i64 {
+            self.window_started_at = now;
+            self.transfers_in_window = 0;
+        }
+        require!(
+            self.transfers_in_window < max_transfers,
+            ErrorCode::RateLimitExceeded
+        );
+        self.transfers_in_window += 1;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawPolicyDreamer<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_dreamer: Account<'info, DreamerState>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_dreamer.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Account<'info, StateConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRateLimitDreamer<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"root_config"],
+        bump = root_config.bump,
+        has_one = admin @ DreamersError::NonRootAccount,
+    )]
+    pub root_config: Account<'info, RootConfig>,
+    #[account(constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated)]
+    pub pda_dreamer: Account<'info, DreamerState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_dreamer.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Account<'info, StateConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRateLimitSwapped<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"root_config"],
+        bump = root_config.bump,
+        has_one = admin @ DreamersError::NonRootAccount,
+    )]
+    pub root_config: Account<'info, RootConfig>,
+    #[account(constraint = pda_swapped.version.is_current() @ ErrorCode::StateNotMigrated)]
+    pub pda_swapped: Account<'info, SwappedState>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_swapped.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Account<'info, StateConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Only the program upgrade authority can create the root config.
+#[derive(Accounts)]
+pub struct InitRootConfig<'info> {
+    #[account(mut)]
+    pub upgrade_authority: Signer<'info>,
+    #[account(
+        seeds = [crate::id().as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::id(),
+        constraint = program_data.upgrade_authority_address == Some(upgrade_authority.key())
+            @ DreamersError::NonRootAccount,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(
+        init,
+        payer = upgrade_authority,
+        space = ROOT_CONFIG_SIZE,
+        seeds = [b"root_config"],
+        bump,
+    )]
+    pub root_config: Account<'info, RootConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRootAdmin<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"root_config"],
+        bump = root_config.bump,
+        has_one = admin @ DreamersError::NonRootAccount,
+    )]
+    pub root_config: Account<'info, RootConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveWithdrawDestination<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_dreamer: Account<'info, DreamerState>,
+    /// CHECK: This is not dangerous because it only seeds the deposit record
+    pub mint: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = DEPOSIT_RECORD_SIZE,
+        seeds = [b"deposit", pda_dreamer.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub deposit_record: Account<'info, DepositRecord>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Fails if `state_config` restricts withdrawals and `destination` is neither the
+/// depositor nor the approved destination in `deposit_record`. Only called for
+/// destinations the state authority picks at transfer time.
+pub fn assert_withdraw_destination(
+    state_config: &AccountInfo,
+    deposit_record: &AccountInfo,
+    destination: &Pubkey,
+) -> Result<()> {
+    match StateConfig::load(state_config)? {
+        Some(config) if config.restrict_to_depositor => {}
+        _ => return Ok(()),
+    }
+    require!(
+        *deposit_record.owner == crate::id() && !deposit_record.data_is_empty(),
+        ErrorCode::InvalidWithdrawDestination
+    );
+    let deposit_record = Account::<DepositRecord>::try_from(deposit_record)?;
+    require!(
+        deposit_record.depositor == *destination
+            || deposit_record.approved_destination == Some(*destination),
+        ErrorCode::InvalidWithdrawDestination
+    );
+    Ok(())
+}
+
+/// Counts a new raffle, auction, listing or vesting schedule of `state`, which
+/// keeps the state from being closed until the position is.
+pub fn record_open_position(state_config: &mut Account<StateConfig>, bump: u8, state: Pubkey) {
+    state_config.bump = bump;
+    state_config.state = state;
+    state_config.open_positions = state_config.open_positions.saturating_add(1);
+}
+
+/// Takes a closed raffle, auction, listing or vesting schedule off the count in
+/// `state_config`.
+pub fn record_closed_position(state_config: &AccountInfo) -> Result<()> {
+    let mut config = match StateConfig::load(state_config)? {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+    config.open_positions = config.open_positions.saturating_sub(1);
+    config.try_serialize(&mut &mut state_config.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+/// Counts a transfer out of the state owning `state_config` against its rate
+/// limit. States without a config are not limited. Only payouts the operator
+/// initiates are limited, buying or delisting a listing is not.
+pub fn consume_rate_limit(state_config: &AccountInfo) -> Result<()> {
+    let mut config = match StateConfig::load(state_config)? {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+    if config.max_transfers_per_window.is_none() {
+        return Ok(());
+    }
+    config.consume_transfer(Clock::get()?.unix_timestamp)?;
+    config.try_serialize(&mut &mut state_config.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+pub fn set_withdraw_policy_dreamer(
+    ctx: Context<SetWithdrawPolicyDreamer>,
+    restrict_to_depositor: bool,
+) -> Result<()> {
+    let state_config = &mut ctx.accounts.state_config;
+    state_config.bump = *ctx.bumps.get("state_config").unwrap();
+    state_config.state = ctx.accounts.pda_dreamer.key();
+    state_config.restrict_to_depositor = restrict_to_depositor;
+    Ok(())
+}
+
+/// Approves `destination` for `mint`, creating the deposit record of assets that
+/// were never deposited through this program.
+pub fn approve_withdraw_destination(
+    ctx: Context<ApproveWithdrawDestination>,
+    destination: Option<Pubkey>,
+) -> Result<()> {
+    let deposit_record = &mut ctx.accounts.deposit_record;
+    deposit_record.bump = *ctx.bumps.get("deposit_record").unwrap();
+    deposit_record.state = ctx.accounts.pda_dreamer.key();
+    deposit_record.mint = ctx.accounts.mint.key();
+    deposit_record.approved_destination = destination;
+    Ok(())
+}
+
+pub fn init_root_config(ctx: Context<InitRootConfig>, admin: Pubkey) -> Result<()> {
+    let root_config = &mut ctx.accounts.root_config;
+    root_config.bump = *ctx.bumps.get("root_config").unwrap();
+    root_config.admin = admin;
+    Ok(())
+}
+
+pub fn set_root_admin(ctx: Context<SetRootAdmin>, admin: Pubkey) -> Result<()> {
+    ctx.accounts.root_config.admin = admin;
+    Ok(())
+}
+
+fn set_rate_limit(
+    state_config: &mut StateConfig,
+    max_transfers_per_window: Option<u32>,
+    rate_limit_window_seconds: u32,
+) -> Result<()> {
+    require!(
+        max_transfers_per_window.is_none() || rate_limit_window_seconds > 0,
+        ErrorCode::InvalidRateLimit
+    );
+    state_config.max_transfers_per_window = max_transfers_per_window;
+    state_config.rate_limit_window_seconds = rate_limit_window_seconds;
+    state_config.window_started_at = 0;
+    state_config.transfers_in_window = 0;
+    Ok(())
+}
+
+pub fn set_rate_limit_dreamer(
+    ctx: Context<SetRateLimitDreamer>,
+    max_transfers_per_window: Option<u32>,
+    rate_limit_window_seconds: u32,
+) -> Result<()> {
+    let state_config = &mut ctx.accounts.state_config;
+    state_config.bump = *ctx.bumps.get("state_config").unwrap();
+    state_config.state = ctx.accounts.pda_dreamer.key();
+    set_rate_limit(state_config, max_transfers_per_window, rate_limit_window_seconds)
+}
+
+pub fn set_rate_limit_swapped(
+    ctx: Context<SetRateLimitSwapped>,
+    max_transfers_per_window: Option<u32>,
+    rate_limit_window_seconds: u32,
+) -> Result<()> {
+    let state_config = &mut ctx.accounts.state_config;
+    state_config.bump = *ctx.bumps.get("state_config").unwrap();
+    state_config.state = ctx.accounts.pda_swapped.key();
+    set_rate_limit(state_config, max_transfers_per_window, rate_limit_window_seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_transfers_per_window: Option<u32>) -> StateConfig {
+        StateConfig {
+            bump: 255,
+            state: Pubkey::new_unique(),
+            restrict_to_depositor: false,
+            allowlist_root: None,
+            allowlist_round: 0,
+            claim_root: None,
+            claim_round: 0,
+            listing_fee_bps: 0,
+            max_transfers_per_window,
+            rate_limit_window_seconds: 100,
+            window_started_at: 0,
+            transfers_in_window: 0,
+            held_assets: 0,
+            open_positions: 0,
+        }
+    }
+
+    #[test]
+    fn unlimited_without_max() {
+        let mut config = config(None);
+        for now in 0..10 {
+            config.consume_transfer(now).unwrap();
+        }
+        assert_eq!(config.transfers_in_window, 0);
+    }
+
+    #[test]
+    fn rejects_transfers_over_max_within_window() {
+        let mut config = config(Some(2));
+        config.consume_transfer(1_000).unwrap();
+        config.consume_transfer(1_050).unwrap();
+        assert_eq!(
+            config.consume_transfer(1_099).unwrap_err(),
+            ErrorCode::RateLimitExceeded.into()
+        );
+        assert_eq!(config.window_started_at, 1_000);
+        assert_eq!(config.transfers_in_window, 2);
+    }
+
+    #[test]
+    fn new_window_starts_once_current_one_has_passed() {
+        let mut config = config(Some(1));
+        config.consume_transfer(1_000).unwrap();
+        assert!(config.consume_transfer(1_099).is_err());
+
+        config.consume_transfer(1_100).unwrap();
+        assert_eq!(config.window_started_at, 1_100);
+        assert_eq!(config.transfers_in_window, 1);
+        assert!(config.consume_transfer(1_199).is_err());
+    }
+
+    #[test]
+    fn window_restarts_at_first_transfer_after_gap() {
+        let mut config = config(Some(1));
+        config.consume_transfer(1_000).unwrap();
+        config.consume_transfer(5_000).unwrap();
+        assert_eq!(config.window_started_at, 5_000);
+        assert!(config.consume_transfer(5_099).is_err());
+    }
+}
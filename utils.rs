@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::TokenAccount;
+use mpl_token_metadata::state::Metadata;
+use mpl_token_metadata::state::TokenMetadataAccount;
+
+use crate::errors::ErrorCode;
+
+pub fn assert_metadata_for_mint(metadata: &AccountInfo, mint: &Pubkey) -> Result<Metadata> {
+    require_keys_eq!(
+        *metadata.owner,
+        mpl_token_metadata::id(),
+        ErrorCode::InvalidMintMetadataOwner
+    );
+    let (metadata_address, _) = mpl_token_metadata::pda::find_metadata_account(mint);
+    require_keys_eq!(metadata.key(), metadata_address, ErrorCode::InvalidMintMetadata);
+
+    let metadata = Metadata::from_account_info(metadata)?;
+    require_keys_eq!(metadata.mint, *mint, ErrorCode::InvalidMintMetadata);
+    Ok(metadata)
+}
+
+/// Creates a program-owned PDA at `marker` with `space` bytes, paid by `payer`.
+pub fn create_marker_account<'info>(
+    marker: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    if marker.lamports() == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                &payer.key(),
+                &marker.key(),
+                rent,
+                space as u64,
+                &crate::id(),
+            ),
+            &[payer.clone(), marker.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+    } else {
+        // the marker address may have been funded up front, which blocks create_account
+        if marker.lamports() < rent {
+            invoke_signed(
+                &system_instruction::transfer(&payer.key(), &marker.key(), rent - marker.lamports()),
+                &[payer.clone(), marker.clone(), system_program.clone()],
+                &[],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(&marker.key(), space as u64),
+            &[marker.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(&marker.key(), &crate::id()),
+            &[marker.clone(), system_program.clone()],
+            &[seeds],
+        )?;
+    }
+    Ok(())
+}
+
+/// Moves lamports out of an account owned by this program, such as an escrow PDA.
+pub fn transfer_lamports_from_pda(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    **from.try_borrow_mut_lamports()? = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    **to.try_borrow_mut_lamports()? = to.lamports().checked_add(amount).unwrap();
+    Ok(())
+}
+
+/// Closes an emptied escrow token account owned by a PDA, returning its rent to
+/// `destination`. Accounts already closed by Token Metadata or still frozen are
+/// left alone.
+pub fn close_escrow_token_account<'info>(
+    token_account: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if token_account.data_is_empty() {
+        return Ok(());
+    }
+    let escrow = TokenAccount::try_deserialize(&mut &token_account.try_borrow_data()?[..])?;
+    if escrow.amount > 0 || escrow.is_frozen() {
+        return Ok(());
+    }
+    invoke_signed(
+        &spl_token::instruction::close_account(
+            &token_program.key(),
+            &token_account.key(),
+            &destination.key(),
+            &authority.key(),
+            &[],
+        )?,
+        &[
+            token_program.clone(),
+            token_account.clone(),
+            destination.clone(),
+            authority.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
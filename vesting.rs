@@ -0,0 +1,431 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::Mint;
+use anchor_spl::token::Token;
+use anchor_spl::token::TokenAccount;
+
+use crate::deposit::record_withdrawal;
+use crate::errors::DreamersError;
+use crate::errors::ErrorCode;
+use crate::pnft::invoke_transfer_asset;
+use crate::pnft::PNFTTransferAccounts;
+use crate::state::DreamerState;
+use crate::state_config::consume_rate_limit;
+use crate::state_config::record_closed_position;
+use crate::state_config::record_open_position;
+use crate::state_config::StateConfig;
+use crate::state_config::STATE_CONFIG_SIZE;
+
+pub const VESTING_MAX_MINTS: usize = 10;
+pub const VESTING_SCHEDULE_SIZE: usize =
+    8 + 1 + 32 + 32 + 8 + 8 + 8 + 8 + (1 + 32) + 8 + 8 + (4 + 32 * VESTING_MAX_MINTS) + 4;
+
+/// Payout from a dreamer PDA that unlocks linearly from `start_time` to
+/// `end_time`, with nothing claimable before `cliff_time`. Pays either
+/// `total_amount` of `token_mint` or the NFTs in `mints`, in order.
+///
+/// Schedules are unbacked and revocable: nothing is escrowed or reserved, the
+/// assets stay in the dreamer PDA where the authority can still transfer them,
+/// and the authority can close the schedule to revoke the unvested remainder.
+/// A claim fails if the dreamer PDA no longer holds what has vested. Only the
+/// vested and unclaimed part is protected, by `close_vesting_schedule`.
+#[account]
+pub struct VestingSchedule {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub recipient: Pubkey,
+    pub identifier: u64,
+    pub start_time: i64,
+    pub cliff_time: i64,
+    pub end_time: i64,
+    /// Fungible mint to pay out, `None` for an NFT schedule
+    pub token_mint: Option<Pubkey>,
+    pub total_amount: u64,
+    pub released_amount: u64,
+    pub mints: Vec<Pubkey>,
+    pub released_mints: u32,
+}
+
+impl VestingSchedule {
+    /// Share of `total` unlocked at `now`.
+    pub fn vested(&self, total: u64, now: i64) -> u64 {
+        if now < self.cliff_time {
+            return 0;
+        }
+        if now >= self.end_time {
+            return total;
+        }
+        let elapsed = (now - self.start_time).max(0) as u128;
+        let duration = (self.end_time - self.start_time) as u128;
+        (total as u128 * elapsed / duration) as u64
+    }
+
+    /// Whether anything has vested at `now` that the recipient has not claimed.
+    pub fn has_unclaimed(&self, now: i64) -> bool {
+        match self.token_mint {
+            Some(_) => self.vested(self.total_amount, now) > self.released_amount,
+            None => self.vested(self.mints.len() as u64, now) > self.released_mints as u64,
+        }
+    }
+}
+
+#[event]
+pub struct VestedClaimed {
+    pub vesting_schedule: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VestingScheduleArgs {
+    pub identifier: u64,
+    pub recipient: Pubkey,
+    pub start_time: i64,
+    pub cliff_time: i64,
+    pub end_time: i64,
+    pub token_mint: Option<Pubkey>,
+    pub total_amount: u64,
+    pub mints: Vec<Pubkey>,
+}
+
+#[derive(Accounts)]
+#[instruction(args: VestingScheduleArgs)]
+pub struct CreateVestingSchedule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_dreamer: Account<'info, DreamerState>,
+    #[account(
+        init,
+        payer = authority,
+        space = VESTING_SCHEDULE_SIZE,
+        seeds = [
+            b"vesting",
+            pda_dreamer.key().as_ref(),
+            args.recipient.as_ref(),
+            &args.identifier.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = STATE_CONFIG_SIZE,
+        seeds = [b"state_config", pda_dreamer.key().as_ref()],
+        bump,
+    )]
+    pub state_config: Account<'info, StateConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVestingSchedule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ DreamersError::Unauthorized,
+        constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_dreamer: Account<'info, DreamerState>,
+    #[account(
+        mut,
+        close = authority,
+        constraint = vesting_schedule.state == pda_dreamer.key() @ ErrorCode::InvalidVestingSchedule,
+    )]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    /// CHECK: This is not dangerous because we only update it in record_closed_position
+    #[account(mut, seeds = [b"state_config", pda_dreamer.key().as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+}
+
+/// For token schedules `mint` is the vesting mint and the pNFT accounts are
+/// ignored, for NFT schedules it is the next mint in the list.
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    #[account(
+        constraint = pda_dreamer.key() == vesting_schedule.state @ ErrorCode::InvalidVestingSchedule,
+        constraint = pda_dreamer.version.is_current() @ ErrorCode::StateNotMigrated,
+    )]
+    pub pda_dreamer: Box<Account<'info, DreamerState>>,
+    #[account(mut, has_one = recipient @ ErrorCode::InvalidVestingSchedule)]
+    pub vesting_schedule: Box<Account<'info, VestingSchedule>>,
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint =
+        token_account.owner == pda_dreamer.key() && token_account.mint == mint.key()
+        @ ErrorCode::InvalidUserOriginalMintTokenAccount)]
+    pub token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we validate it in consume_rate_limit
+    #[account(mut, seeds = [b"state_config", pda_dreamer.key().as_ref()], bump)]
+    pub state_config: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we only update it in record_withdrawal
+    #[account(mut, seeds = [b"deposit", pda_dreamer.key().as_ref(), mint.key().as_ref()], bump)]
+    pub deposit_record: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub recipient_token_record: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we validate it in invoke_transfer_asset
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub edition: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub sysvar_instructions: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(address = mpl_token_metadata::id())]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub authorization_rules_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Records a schedule for assets the dreamer PDA holds. Nothing is moved or
+/// reserved, see `VestingSchedule` for what the recipient can rely on.
+pub fn create_vesting_schedule(
+    ctx: Context<CreateVestingSchedule>,
+    args: VestingScheduleArgs,
+) -> Result<()> {
+    require!(
+        args.start_time < args.end_time
+            && args.start_time <= args.cliff_time
+            && args.cliff_time <= args.end_time,
+        ErrorCode::InvalidVestingSchedule
+    );
+    match args.token_mint {
+        Some(_) => require!(
+            args.total_amount > 0 && args.mints.is_empty(),
+            ErrorCode::InvalidVestingSchedule
+        ),
+        None => require!(
+            !args.mints.is_empty() && args.mints.len() <= VESTING_MAX_MINTS,
+            ErrorCode::InvalidVestingSchedule
+        ),
+    }
+
+    let vesting_schedule = &mut ctx.accounts.vesting_schedule;
+    vesting_schedule.bump = *ctx.bumps.get("vesting_schedule").unwrap();
+    vesting_schedule.state = ctx.accounts.pda_dreamer.key();
+    vesting_schedule.recipient = args.recipient;
+    vesting_schedule.identifier = args.identifier;
+    vesting_schedule.start_time = args.start_time;
+    vesting_schedule.cliff_time = args.cliff_time;
+    vesting_schedule.end_time = args.end_time;
+    vesting_schedule.token_mint = args.token_mint;
+    vesting_schedule.total_amount = args.total_amount;
+    vesting_schedule.released_amount = 0;
+    vesting_schedule.mints = args.mints;
+    vesting_schedule.released_mints = 0;
+
+    record_open_position(
+        &mut ctx.accounts.state_config,
+        *ctx.bumps.get("state_config").unwrap(),
+        ctx.accounts.pda_dreamer.key(),
+    );
+    Ok(())
+}
+
+/// Revokes the schedule. The unvested remainder stays with the dreamer PDA, the
+/// recipient has to claim what has already vested before it can be closed.
+pub fn close_vesting_schedule(ctx: Context<CloseVestingSchedule>) -> Result<()> {
+    require!(
+        !ctx.accounts.vesting_schedule.has_unclaimed(Clock::get()?.unix_timestamp),
+        ErrorCode::VestedNotClaimed
+    );
+    record_closed_position(&ctx.accounts.state_config)
+}
+
+/// Releases whatever has vested and not been claimed. Token schedules pay the
+/// full unlocked amount, NFT schedules pay the next unlocked mint per call.
+pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+    msg!(format!("Claiming Vested Rewards From Dreamer Account").as_str());
+
+    let now = Clock::get()?.unix_timestamp;
+    let pda_dreamer = &ctx.accounts.pda_dreamer;
+    let dreamer_seeds: &[&[u8]] = &[
+        b"dreamer",
+        &[pda_dreamer.id],
+        pda_dreamer.authority.as_ref(),
+        &[pda_dreamer.bump],
+    ];
+    let vesting_schedule = &ctx.accounts.vesting_schedule;
+    let mint = ctx.accounts.mint.key();
+    consume_rate_limit(&ctx.accounts.state_config)?;
+
+    let amount = match vesting_schedule.token_mint {
+        Some(token_mint) => {
+            require_keys_eq!(mint, token_mint, ErrorCode::InvalidVestingMint);
+            let amount = vesting_schedule
+                .vested(vesting_schedule.total_amount, now)
+                .saturating_sub(vesting_schedule.released_amount);
+            require!(amount > 0, ErrorCode::NothingVested);
+
+            invoke_signed(
+                &spl_token::instruction::transfer(
+                    &ctx.accounts.token_program.key(),
+                    &ctx.accounts.token_account.key(),
+                    &ctx.accounts.recipient_token_account.key(),
+                    &pda_dreamer.key(),
+                    &[],
+                    amount,
+                )?,
+                &[
+                    ctx.accounts.token_program.to_account_info(),
+                    ctx.accounts.token_account.to_account_info(),
+                    ctx.accounts.recipient_token_account.to_account_info(),
+                    pda_dreamer.to_account_info(),
+                ],
+                &[dreamer_seeds],
+            )?;
+            amount
+        }
+        None => {
+            let released = vesting_schedule.released_mints as usize;
+            let unlocked = vesting_schedule.vested(vesting_schedule.mints.len() as u64, now);
+            require!(unlocked as usize > released, ErrorCode::NothingVested);
+            require_keys_eq!(
+                mint,
+                vesting_schedule.mints[released],
+                ErrorCode::InvalidVestingMint
+            );
+
+            invoke_transfer_asset(
+                &PNFTTransferAccounts {
+                    token_account: ctx.accounts.token_account.to_account_info(),
+                    token_owner: pda_dreamer.to_account_info(),
+                    destination_token_account: ctx.accounts.recipient_token_account.to_account_info(),
+                    destination: ctx.accounts.recipient.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    edition: ctx.accounts.edition.to_account_info(),
+                    token_record: ctx.accounts.token_record.to_account_info(),
+                    destination_token_record: ctx.accounts.recipient_token_record.to_account_info(),
+                    authority: pda_dreamer.to_account_info(),
+                    payer: ctx.accounts.recipient.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+                    authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+                    authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+                },
+                &[dreamer_seeds],
+            )?;
+            1
+        }
+    };
+    // `token_account` holds the balance from before the transfer, so this is
+    // true once the dreamer PDA has paid out all it held of `mint`
+    if ctx.accounts.token_account.amount == amount {
+        record_withdrawal(&ctx.accounts.deposit_record, &ctx.accounts.state_config)?;
+    }
+
+    let vesting_schedule = &mut ctx.accounts.vesting_schedule;
+    if vesting_schedule.token_mint.is_some() {
+        vesting_schedule.released_amount = vesting_schedule
+            .released_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::InvalidVestingSchedule)?;
+    } else {
+        vesting_schedule.released_mints = vesting_schedule
+            .released_mints
+            .checked_add(1)
+            .ok_or(ErrorCode::InvalidVestingSchedule)?;
+    }
+
+    emit!(VestedClaimed {
+        vesting_schedule: vesting_schedule.key(),
+        recipient: vesting_schedule.recipient,
+        mint,
+        amount,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(token_mint: Option<Pubkey>, mints: Vec<Pubkey>) -> VestingSchedule {
+        VestingSchedule {
+            bump: 255,
+            state: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            identifier: 0,
+            start_time: 1_000,
+            cliff_time: 1_250,
+            end_time: 2_000,
+            token_mint,
+            total_amount: 1_000,
+            released_amount: 0,
+            mints,
+            released_mints: 0,
+        }
+    }
+
+    #[test]
+    fn nothing_vests_before_cliff() {
+        let schedule = schedule(Some(Pubkey::new_unique()), vec![]);
+        assert_eq!(schedule.vested(1_000, 0), 0);
+        assert_eq!(schedule.vested(1_000, 1_000), 0);
+        assert_eq!(schedule.vested(1_000, 1_249), 0);
+    }
+
+    #[test]
+    fn vests_linearly_from_start_at_cliff() {
+        let schedule = schedule(Some(Pubkey::new_unique()), vec![]);
+        assert_eq!(schedule.vested(1_000, 1_250), 250);
+        assert_eq!(schedule.vested(1_000, 1_500), 500);
+        assert_eq!(schedule.vested(1_000, 1_999), 999);
+    }
+
+    #[test]
+    fn fully_vested_at_and_after_end() {
+        let schedule = schedule(Some(Pubkey::new_unique()), vec![]);
+        assert_eq!(schedule.vested(1_000, 2_000), 1_000);
+        assert_eq!(schedule.vested(1_000, i64::MAX), 1_000);
+    }
+
+    #[test]
+    fn unclaimed_tracks_released_amount() {
+        let mut schedule = schedule(Some(Pubkey::new_unique()), vec![]);
+        assert!(!schedule.has_unclaimed(1_249));
+        assert!(schedule.has_unclaimed(1_250));
+        schedule.released_amount = 250;
+        assert!(!schedule.has_unclaimed(1_250));
+        assert!(schedule.has_unclaimed(2_000));
+    }
+
+    #[test]
+    fn unclaimed_tracks_released_mints() {
+        let mints = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let mut schedule = schedule(None, mints);
+        assert!(schedule.has_unclaimed(1_250));
+        schedule.released_mints = 1;
+        assert!(!schedule.has_unclaimed(1_499));
+        assert!(schedule.has_unclaimed(1_500));
+        schedule.released_mints = 4;
+        assert!(!schedule.has_unclaimed(2_000));
+    }
+}